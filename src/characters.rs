@@ -1,15 +1,17 @@
-use std::{collections::HashMap, path::Path, sync::LazyLock};
+use std::{collections::{HashMap, HashSet}, path::Path, sync::LazyLock};
 
 use crossbeam::channel::Sender;
-use ievr_cfg_bin_editor_core::{Row, Table, Value};
 
 mod character;
 
 pub use character::Character;
 use rusqlite::{Connection, Result, params};
 
-use crate::common::{parse_byte_value, parse_int_value, parse_uint_value};
-pub use crate::{characters::character::{Element, Position, Stats, Style}, common::parse_gamefile};
+pub use crate::characters::character::{Element, MAX_LEVEL, Position, Rarity, Stats, Style};
+use crate::common::{GameFile, GameRow, GameTable};
+use crate::file_operations::{FileRules, ResolveError};
+use crate::schema;
+use crate::validation::{MiningError, MiningErrorKind, ValidationReport};
 
 pub const CHARA_ROOT_PATH: &str = "data/common/gamedata/character/";
 
@@ -22,82 +24,108 @@ pub static CHARA_REQUIRED_FILES: LazyLock<HashMap<&'static str, &'static str>> =
     map
 });
 
-pub fn populate_character_data(extraction_path: &Path, mut character_database_connection: Connection, requested_files: HashMap<&'static str, String>, char_name_req_tx: Sender<(i32, i32)>) {
-    // Database operations
-    initialize_database(&character_database_connection).unwrap();
+static CHARA_RULES: LazyLock<FileRules> = LazyLock::new(|| FileRules::new(&CHARA_REQUIRED_FILES));
+
+/// The names of the character files resolved in the extraction folder.
+#[derive(Debug, Clone)]
+pub struct CharacterFiles {
+    pub chara_base: String,
+    pub chara_param: String,
+    pub chara_series: String,
+    pub growth_table: String,
+}
 
-    character_database_connection.pragma_update(None, "journal_mode", "WAL").unwrap();
-    character_database_connection.pragma_update(None, "synchronous", "NORMAL").unwrap();
+impl CharacterFiles {
+    pub fn resolve(extraction_root: &Path) -> Result<CharacterFiles, ResolveError> {
+        let mut files = CHARA_RULES.resolve(extraction_root, Path::new(CHARA_ROOT_PATH))?.into_result()?;
+
+        Ok(CharacterFiles {
+            chara_base: files.take("chara_base"),
+            chara_param: files.take("chara_param"),
+            chara_series: files.take("chara_series"),
+            growth_table: files.take("growth_table"),
+        })
+    }
+
+    pub fn file_names(&self) -> [&str; 4] {
+        [&self.chara_base, &self.chara_param, &self.chara_series, &self.growth_table]
+    }
+}
 
+/// Everything parsed from the character game files.
+#[derive(Debug)]
+pub struct CharacterData {
+    /// Every valid character, heroes and basaras included, sorted by index
+    pub characters: Vec<Character>,
+    /// Maps the series ID stored in chara_base to the series text ID
+    pub series: HashMap<i32, i32>,
+    /// The number of chara_base entries without any valid chara_param entry
+    pub ignored_characters: u32,
+}
+
+pub fn load_character_data(extraction_path: &Path, files: &CharacterFiles, report: &mut ValidationReport) -> Result<CharacterData, MiningError> {
     // We parse the game files
     let root_path= extraction_path.to_path_buf().join(CHARA_ROOT_PATH);
 
-    let chara_base = parse_gamefile(&root_path.join(&requested_files["chara_base"])).unwrap();
-    let chara_base_info = chara_base.table("CHARA_BASE_INFO").unwrap();
+    let chara_base = GameFile::open(&root_path.join(&files.chara_base))?;
+    let chara_base_info = chara_base.table(&schema::CHARA_BASE_INFO, report)?;
 
-    let chara_param = parse_gamefile(&root_path.join(&requested_files["chara_param"])).unwrap();
-    let chara_param_info = chara_param.table("CHARA_PARAM_INFO").unwrap();
+    let chara_param = GameFile::open(&root_path.join(&files.chara_param))?;
+    let chara_param_info = chara_param.table(&schema::CHARA_PARAM_INFO, report)?;
 
-    let chara_series_config = parse_gamefile(&root_path.join(&requested_files["chara_series"])).unwrap();
-    let chara_series_config_table = chara_series_config.table("m_charaSeriesInfoList").unwrap();
+    let chara_series_config = GameFile::open(&root_path.join(&files.chara_series))?;
+    let chara_series_config_table = chara_series_config.table(&schema::CHARA_SERIES_INFO, report)?;
 
-    let growth_table = parse_gamefile(&root_path.join(&requested_files["growth_table"])).unwrap();
-    let growth_table_main = growth_table.table("m_growthTableMainList").unwrap();
+    let growth_table = GameFile::open(&root_path.join(&files.growth_table))?;
+    let growth_table_main = growth_table.table(&schema::GROWTH_TABLE_MAIN, report)?;
 
     // We create helper data structures to facilitate extraction
-    let growth_hash_table = parse_growth_table(growth_table_main);    
+    let growth_hash_table = parse_growth_table(growth_table_main, report)?;    
 
-    let series_text_hash_table = parse_series_info(chara_series_config_table);
+    let series_text_hash_table = parse_series_info(chara_series_config_table, report)?;
 
-    let chara_base_info = get_characters(&chara_base_info);
+    let chara_params = group_chara_params(chara_param_info, report)?;
+
+    let chara_base_info = get_characters(chara_base_info, report)?;
 
     #[cfg(debug_assertions)]
     println!("Nb of characters: {}", chara_base_info.len());
 
-    // We create the buffers to batch insertions
-    let mut char_buffer = Vec::with_capacity(1000);
-    let mut hero_buffer = Vec::with_capacity(100);
-    let mut basara_buffer = Vec::with_capacity(100);
-
+    let mut characters: Vec<Character> = Vec::with_capacity(chara_base_info.len());
     let mut ignored_characters = 0;
 
-    for row in chara_base_info {
-        let index = parse_int_value(&row.values[2][0]);
+    // The position of every character in `characters`, by index and rarity
+    let mut positions = HashMap::new();
 
-        let name_id = parse_int_value(&row.values[3][0]);
-        let description_id = parse_int_value(&row.values[19][0]);
+    for (index, row) in chara_base_info {
+        let ids = (|| Ok((row.int("id")?, row.int("name_id")?, row.int("description_id")?, row.int("series_id")?)))();
+        let Some((chara_base_id, name_id, description_id, series_id)) = report.record(ids)? else { continue };
 
-        let series_id = match series_text_hash_table.get(&parse_int_value(&row.values[15][0])) {
+        let series_id = match series_text_hash_table.get(&series_id) {
             Some(v) => *v,
             None => continue, // If the character doesn't have a valid series ID it is not worth investigating
         };
 
-        let chara_base_id = parse_int_value(&row.values[0][0]);
         let mut found_char = false;
 
-        for row in chara_param_info.rows() {
-            if parse_int_value(&row.values[1][0]) == chara_base_id {                
-                if let Some(character) = extract_character(index, name_id, description_id, series_id, row, &growth_hash_table) {
-                    let rarity = parse_int_value(&row.values[41][0]);
-
-                    if !found_char {
-                        char_name_req_tx.send((name_id, description_id)).unwrap();
-                        found_char = true;
-                    }
-                
-                    match rarity {
-                        0 => char_buffer.push(character),
-                        5..8 => hero_buffer.push(character), // insert_character(&mut hero_stmt, &character),
-                        8 => basara_buffer.push(character),// insert_character(&mut basara_stmt, &character),
-                        _ => unreachable!()
-                    };
-                } 
+        for row in chara_params.get(&chara_base_id).into_iter().flatten() {
+            let character = extract_character(index, chara_base_id, name_id, description_id, series_id, row, &growth_hash_table);
+
+            if let Some(Some(character)) = report.record(character)? {
+                found_char = true;
+
+                // Like for the texts, the last row defining a character is kept
+                match positions.get(&(character.index, character.rarity)) {
+                    Some(&position) => {
+                        report.warn(row.error(Some("rarity"), MiningErrorKind::DuplicateCharacter { index: character.index, rarity: character.rarity }));
+                        characters[position] = character;
+                    },
+                    None => {
+                        positions.insert((character.index, character.rarity), characters.len());
+                        characters.push(character);
+                    },
+                }
             }
-
-            if char_buffer.len() >= 1000 {
-                insert_characters(&mut character_database_connection, &char_buffer).unwrap();
-                char_buffer.clear();
-            } 
         }
 
         if !found_char {
@@ -105,115 +133,182 @@ pub fn populate_character_data(extraction_path: &Path, mut character_database_co
         }
     };
 
-    insert_characters(&mut character_database_connection, &char_buffer).unwrap();
-    insert_heroes(&mut character_database_connection, &hero_buffer).unwrap();
-    insert_basaras(&mut character_database_connection, &basara_buffer).unwrap();
+    Ok(CharacterData { characters, series: series_text_hash_table, ignored_characters })
+}
+
+/// Writes the parsed characters into the database, and requests their
+/// names and descriptions from the text thread.
+pub fn write_character_data(character_database_connection: &mut Connection, characters: &[Character], portraits: &HashMap<i32, String>, char_name_req_tx: Sender<(i32, i32)>) -> Result<()> {
+    // Database operations
+    initialize_database(character_database_connection)?;
+
+    character_database_connection.pragma_update(None, "journal_mode", "WAL")?;
+    character_database_connection.pragma_update(None, "synchronous", "NORMAL")?;
+
+    // We create the buffers to batch insertions
+    let mut char_buffer = Vec::with_capacity(1000);
+    let mut skill_buffer = Vec::with_capacity(6000);
+
+    let mut requests = HashSet::new();
+
+    for character in characters {
+        // The texts of every rarity are requested, each pair only once
+        if requests.insert((character.name_id, character.description_id)) {
+            // The text thread only hangs up after failing, which it reports itself
+            let _ = char_name_req_tx.send((character.name_id, character.description_id));
+        }
+
+        for (slot, skill_id) in character.skills.iter().enumerate() {
+            if *skill_id != 0 { // Empty technique path slots are stored as 0
+                skill_buffer.push((character.index, character.rarity, slot as i32, *skill_id));
+            }
+        }
+
+        char_buffer.push(character);
+
+        if char_buffer.len() >= 1000 {
+            insert_characters(character_database_connection, &char_buffer, portraits)?;
+            char_buffer.clear();
+        } 
+    }
 
-    println!("[CHARACTERS]: {ignored_characters} character(s) ignored for being invalid.");
+    insert_characters(character_database_connection, &char_buffer, portraits)?;
+    insert_character_skills(character_database_connection, &skill_buffer)?;
+
+    Ok(())
 }
 
-fn get_characters(chara_base_info: &Table) -> Vec<&Row> {
-    let mut filtered_table: Vec<&Row> = chara_base_info.rows().iter()
-        .filter(|row| match row.values[2][0] {
-            Value::Int(v) => v > 0, // We only want the characters who have a valid index
-            _ => unreachable!()
-        })
-        .collect();
+/// Returns the rows of the characters with a valid index, with their index.
+fn get_characters<'a>(chara_base_info: GameTable<'a>, report: &mut ValidationReport) -> Result<Vec<(i32, GameRow<'a>)>, MiningError> {
+    let mut filtered_table: Vec<(i32, GameRow)> = Vec::new();
+
+    for row in chara_base_info.rows() {
+        if let Some(index) = report.record(row.int("index"))? 
+            && index > 0 // We only want the characters who have a valid index
+        {
+            filtered_table.push((index, row));
+        }
+    }
+
+    filtered_table.sort_by_key(|(index, _)| *index); // We also sort by index while we're at it
+
+    Ok(filtered_table)
+}
+
+/// Groups the rows of chara_param by the chara_base ID they belong
+/// to, every rarity of a character having its own row.
+fn group_chara_params<'a>(chara_param_info: GameTable<'a>, report: &mut ValidationReport) -> Result<HashMap<i32, Vec<GameRow<'a>>>, MiningError> {
+    let mut chara_params: HashMap<i32, Vec<GameRow>> = HashMap::new();
 
-    filtered_table.sort_by_key(|row| {
-        match row.values[2][0] {
-            Value::Int(v) => v, // We also sort by index while we're at it
-            _ => unreachable!()
+    for row in chara_param_info.rows() {
+        if let Some(chara_base_id) = report.record(row.int("chara_base_id"))? {
+            chara_params.entry(chara_base_id).or_default().push(row);
         }
-    });
+    }
 
-    filtered_table
+    Ok(chara_params)
 }
 
-fn parse_growth_table(growth_table_main: &Table) -> HashMap<(u8, u8, u8), (Stats, Stats)> {
+/// The level 50 and 99 stats of the growth table entries, by main position, growth pattern and rank
+type GrowthTable = HashMap<(u8, u8, u8), (Stats, Stats)>;
+
+fn parse_growth_table(growth_table_main: GameTable, report: &mut ValidationReport) -> Result<GrowthTable, MiningError> {
     let mut growth_hash_table = HashMap::new();
 
+    let stats = |row: &GameRow, level: u8| -> Result<Stats, MiningError> {
+        let stat = |name: &str| row.int(&format!("lvl{level}_{name}")).map(|value| value as u16);
+
+        Ok(Stats {
+            kick:           stat("kick")?,
+            control:        stat("control")?,
+            technique:      stat("technique")?,
+            pressure:       stat("pressure")?,
+            physical:       stat("physical")?,
+            agility:        stat("agility")?,
+            intelligence:   stat("intelligence")?,
+        })
+    };
+
     for row in growth_table_main.rows() {
-        let main_position = parse_byte_value(&row.values[0][0]);
-        let growth_pattern = parse_byte_value(&row.values[1][0]);
-        let chara_rank = parse_byte_value(&row.values[2][0]);
-
-        growth_hash_table.insert((main_position, growth_pattern, chara_rank), 
-            (Stats { // The level 50 stats for legendary, heroes and basara characters are multiplied by 1.4 from the game's base stats
-                kick:           parse_int_value(&row.values[3][0]) as u16 * 14 / 10,
-                control:        parse_int_value(&row.values[4][0]) as u16 * 14 / 10,
-                technique:      parse_int_value(&row.values[5][0]) as u16 * 14 / 10,
-                pressure:       parse_int_value(&row.values[6][0]) as u16 * 14 / 10,
-                physical:       parse_int_value(&row.values[7][0]) as u16 * 14 / 10,
-                agility:        parse_int_value(&row.values[8][0]) as u16 * 14 / 10,
-                intelligence:   parse_int_value(&row.values[9][0]) as u16 * 14 / 10,
-            }, 
-            Stats { // These are the base stats, the exact computation for level 99 is unknown
-                kick:           parse_int_value(&row.values[10][0]) as u16,
-                control:        parse_int_value(&row.values[11][0]) as u16,
-                technique:      parse_int_value(&row.values[12][0]) as u16,
-                pressure:       parse_int_value(&row.values[13][0]) as u16,
-                physical:       parse_int_value(&row.values[14][0]) as u16,
-                agility:        parse_int_value(&row.values[15][0]) as u16,
-                intelligence:   parse_int_value(&row.values[16][0]) as u16,
-            })
-        );
+        let entry = (|| {
+            let key = (row.byte("main_position")?, row.byte("growth_pattern")?, row.byte("chara_rank")?);
+
+            Ok((key, (
+                // The level 50 stats for legendary, heroes and basara characters are multiplied by 1.4 from the game's base stats
+                stats(&row, 50)?.map(|stat| stat * 14 / 10),
+                // These are the base stats, the exact computation for level 99 is unknown
+                stats(&row, 99)?,
+            )))
+        })();
+
+        if let Some((key, growth)) = report.record(entry)? {
+            growth_hash_table.insert(key, growth);
+        }
     };
 
-    growth_hash_table
+    Ok(growth_hash_table)
 }
 
 /// This functions creates a hash map to map the series ID stored in
 /// chara_base to the actual series text ID that is stored in the
 /// corresponding text file.
-fn parse_series_info(chara_series_config_table: &Table) -> HashMap<i32, i32> {
+fn parse_series_info(chara_series_config_table: GameTable, report: &mut ValidationReport) -> Result<HashMap<i32, i32>, MiningError> {
     let mut series_hash_map = HashMap::with_capacity(9);
 
     for row in chara_series_config_table.rows() {
-        // The game stores this value as unsigned but chara_base stores it as signed...
-        let series_id = parse_uint_value(&row.values[0][0]).cast_signed();
+        // The game stores these values as unsigned, the series ID in chara_base 
+        // and the text ID in the text file being signed...
+        let ids = (|| Ok((row.uint("series_id")?.cast_signed(), row.uint("text_id")?.cast_signed())))();
 
-        // Same for the text file
-        let series_text_id = parse_uint_value(&row.values[2][0]).cast_signed();  
-        
-        series_hash_map.insert(series_id, series_text_id);
+        if let Some((series_id, series_text_id)) = report.record(ids)? {
+            series_hash_map.insert(series_id, series_text_id);
+        }
     }
 
-    series_hash_map
+    Ok(series_hash_map)
 }
 
-fn extract_character(index: i32, name_id: i32, description_id: i32, series_id: i32, row: &Row, growth_hash_table: &HashMap<(u8, u8, u8), (Stats, Stats)>) -> Option<Character> {
-    let rarity = parse_int_value(&row.values[41][0]);
+/// Returns `None` for the characters that are filtered out, and an
+/// error for the rows holding unexpected data.
+fn extract_character(index: i32, chara_base_id: i32, name_id: i32, description_id: i32, series_id: i32, row: &GameRow, growth_hash_table: &GrowthTable) -> Result<Option<Character>, MiningError> {
+    let chara_param_id = row.int("id")?;
+    let rarity = row.int("rarity")?;
+
+    let rarity_kind = Rarity::from(rarity);
+    if rarity_kind == Rarity::UNKNOWN {
+        return Err(row.error(Some("rarity"), MiningErrorKind::UnknownRarity { rarity }));
+    }
 
-    let skill_slice: Vec<i32> = row.values[23..=28].iter()// We filter by making sure the character has a second technique path
-        .flatten()
-        .map(parse_int_value)
-        .collect();
+    let skill_slice = row.ints(&["skill_1", "skill_2", "skill_3", "skill_4", "skill_5", "skill_6"])?;
 
-    if (rarity == 0 || rarity == 8 ) &&                 // Heroes do not have a second technique path
-        skill_slice.iter().any(|v| *v == 0) { 
-            return None 
+    if (rarity == 0 || rarity == 8 ) &&                 // We filter by making sure the character has a second technique path,
+        skill_slice.contains(&0) {                      // heroes do not have one
+            return Ok(None) 
         } 
 
-    let element = Element::from(parse_int_value(&row.values[2][0]));
-    let main_position = Position::from(parse_int_value(&row.values[3][0]));
-    let alt_position = Position::from(parse_int_value(&row.values[4][0]));
-    let style = Style::from(parse_int_value(&row.values[5][0]));
+    let element = Element::from(row.int("element")?);
+    let main_position = Position::from(row.int("main_position")?);
+    let alt_position = Position::from(row.int("alt_position")?);
+    let style = Style::from(row.int("style")?);
 
-    let growth_pattern = parse_int_value(&row.values[7][0]) as u8;
+    let growth_pattern = row.int("growth_pattern")? as u8;
 
-    let chara_rank = parse_int_value(&row.values[9][0]) as u8;
+    let chara_rank = row.int("chara_rank")? as u8;
 
     let (lvl50_stats, lvl99_stats) = if main_position != Position::UNKNOWN {
         match growth_hash_table.get(&(main_position as u8, growth_pattern, chara_rank)) {
             Some(v) => *v,
-            None => unreachable!()
+            None => return Err(row.error(None, MiningErrorKind::UnknownGrowthEntry { 
+                main_position: main_position as u8, 
+                growth_pattern, 
+                chara_rank 
+            })),
         }
     } else {
         (Stats::default(), Stats::default())
     };
 
-    Some(Character {
+    Ok(Some(Character {
         index,
         name_id,
         description_id,
@@ -224,13 +319,38 @@ fn extract_character(index: i32, name_id: i32, description_id: i32, series_id: i
         lvl50_stats,
         lvl99_stats,
         series_id,
-    })
+        chara_base_id,
+        chara_param_id,
+        rarity,
+        growth_pattern,
+        chara_rank,
+        skills: skill_slice,
+    }))
 }
 
 fn initialize_database(database: &Connection) -> Result<()> {
+    // Older versions stored the stats in the characters table, and the heroes and
+    // basaras in their own tables, which are now views
+    let legacy_characters: bool = database.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('characters') WHERE name = 'lvl50_kick'", 
+        [], 
+        |row| row.get(0)
+    )?;
+
+    if legacy_characters {
+        database.execute("DROP TABLE characters;", ())?;
+    }
+
+    drop_legacy_table(database, "heroes")?;
+    drop_legacy_table(database, "basaras")?;
+
+    // Every rarity of a character is a row, identified by its index and rarity
     database.execute(
         "CREATE TABLE IF NOT EXISTS characters (
-            index_id        INTEGER PRIMARY KEY,
+            index_id        INTEGER NOT NULL,
+            rarity          INTEGER NOT NULL,
+            chara_base_id   INTEGER NOT NULL,
+            chara_param_id  INTEGER NOT NULL,
             name_id         INTEGER NOT NULL,
             description_id  INTEGER NOT NULL,
             element         INTEGER NOT NULL,
@@ -238,161 +358,166 @@ fn initialize_database(database: &Connection) -> Result<()> {
             alt_position    INTEGER NOT NULL,
             style           INTEGER NOT NULL,
             series_id       INTEGER NOT NULL,
+            growth_pattern  INTEGER NOT NULL,
+            chara_rank      INTEGER NOT NULL,
+            portrait_path   TEXT,
 
-            lvl50_kick          INTEGER,
-            lvl50_control       INTEGER,
-            lvl50_technique     INTEGER,
-            lvl50_pressure      INTEGER,
-            lvl50_physical      INTEGER,
-            lvl50_agility       INTEGER,
-            lvl50_intelligence  INTEGER,
-
-            lvl99_kick          INTEGER,
-            lvl99_control       INTEGER,
-            lvl99_technique     INTEGER,
-            lvl99_pressure      INTEGER,
-            lvl99_physical      INTEGER,
-            lvl99_agility       INTEGER,
-            lvl99_intelligence  INTEGER
+            PRIMARY KEY (index_id, rarity)
         );", 
     ()
     )?;
 
+    add_missing_column(database, "characters", "portrait_path", "TEXT")?;
+
     database.execute(
-        "CREATE TABLE IF NOT EXISTS heroes (
+        "CREATE TABLE IF NOT EXISTS character_stats (
             index_id        INTEGER NOT NULL,
-            name_id         INTEGER NOT NULL,
-            description_id  INTEGER NOT NULL,
-            element         INTEGER NOT NULL,
-            main_position   INTEGER NOT NULL,
-            alt_position    INTEGER NOT NULL,
-            style           INTEGER NOT NULL,
-            series_id       INTEGER NOT NULL,
-
-            lvl50_kick          INTEGER,
-            lvl50_control       INTEGER,
-            lvl50_technique     INTEGER,
-            lvl50_pressure      INTEGER,
-            lvl50_physical      INTEGER,
-            lvl50_agility       INTEGER,
-            lvl50_intelligence  INTEGER,
-
-            lvl99_kick          INTEGER,
-            lvl99_control       INTEGER,
-            lvl99_technique     INTEGER,
-            lvl99_pressure      INTEGER,
-            lvl99_physical      INTEGER,
-            lvl99_agility       INTEGER,
-            lvl99_intelligence  INTEGER
+            rarity          INTEGER NOT NULL,
+            level           INTEGER NOT NULL,
+
+            kick            INTEGER NOT NULL,
+            control         INTEGER NOT NULL,
+            technique       INTEGER NOT NULL,
+            pressure        INTEGER NOT NULL,
+            physical        INTEGER NOT NULL,
+            agility         INTEGER NOT NULL,
+            intelligence    INTEGER NOT NULL,
+
+            PRIMARY KEY (index_id, rarity, level),
+            FOREIGN KEY (index_id, rarity) REFERENCES characters (index_id, rarity)
         );", 
     ()
     )?;
 
     database.execute(
-        "CREATE TABLE IF NOT EXISTS basaras (
+        "CREATE TABLE IF NOT EXISTS character_skills (
             index_id        INTEGER NOT NULL,
-            name_id         INTEGER NOT NULL,
-            description_id  INTEGER NOT NULL,
-            element         INTEGER NOT NULL,
-            main_position   INTEGER NOT NULL,
-            alt_position    INTEGER NOT NULL,
-            style           INTEGER NOT NULL,
-            series_id       INTEGER NOT NULL,
+            rarity          INTEGER NOT NULL,
+            slot            INTEGER NOT NULL,
+            skill_id        INTEGER NOT NULL,
 
-            lvl50_kick          INTEGER,
-            lvl50_control       INTEGER,
-            lvl50_technique     INTEGER,
-            lvl50_pressure      INTEGER,
-            lvl50_physical      INTEGER,
-            lvl50_agility       INTEGER,
-            lvl50_intelligence  INTEGER,
-
-            lvl99_kick          INTEGER,
-            lvl99_control       INTEGER,
-            lvl99_technique     INTEGER,
-            lvl99_pressure      INTEGER,
-            lvl99_physical      INTEGER,
-            lvl99_agility       INTEGER,
-            lvl99_intelligence  INTEGER
+            PRIMARY KEY (index_id, rarity, slot),
+            FOREIGN KEY (index_id, rarity) REFERENCES characters (index_id, rarity)
         );", 
     ()
     )?;
 
+    // The views belong to the dataminer, so they are recreated in case their definition changed.
+    // The legendaries, heroes and basaras views keep the layout of the former tables.
+    database.execute_batch(&format!(
+        "{}
+        {}
+        {}",
+        rarity_view("legendaries", "rarity = 0"),
+        rarity_view("heroes", "rarity BETWEEN 5 AND 7"),
+        rarity_view("basaras", "rarity = 8"),
+    ))?;
+
+    // When mining incrementally, the tables already exist: we only replace their
+    // content, so that anything the user built on top of them is kept
+    database.execute_batch(
+        "DELETE FROM character_stats;
+        DELETE FROM character_skills;
+        DELETE FROM characters;"
+    )?;
+
     Ok(())
 }
 
-fn insert_characters(conn: &mut Connection, characters: &Vec<Character>) -> rusqlite::Result<()> {
-    let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Exclusive)?;
+/// Adds a column to a table created by an older version of the dataminer,
+/// which is kept when mining incrementally.
+fn add_missing_column(database: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let exists: bool = database.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2", 
+        [table, column], 
+        |row| row.get(0)
+    )?;
 
-    {
-        let mut stmt = tx.prepare_cached(
-            "INSERT INTO characters (
-                index_id, name_id, description_id, element, main_position, alt_position, style, series_id,
-                lvl50_kick, lvl50_control, lvl50_technique, lvl50_pressure,
-                lvl50_physical, lvl50_agility, lvl50_intelligence,
-                lvl99_kick, lvl99_control, lvl99_technique, lvl99_pressure,
-                lvl99_physical, lvl99_agility, lvl99_intelligence
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 
-                        ?9, ?10, ?11, ?12, ?13, ?14, ?15, 
-                        ?16, ?17, ?18, ?19, ?20, ?21, ?22)
-            ON CONFLICT(index_id) DO NOTHING"
-        )?;
+    if !exists {
+        database.execute(&format!("ALTER TABLE {table} ADD COLUMN {column} {definition};"), ())?;
+    }
 
-        for c in characters {
-            stmt.execute(
-                params![
-                    c.index,
-                    c.name_id,
-                    c.description_id,
-                    c.element as i32,
-                    c.main_position as i32,
-                    c.alt_position as i32,
-                    c.style as i32,
-                    c.series_id,
+    Ok(())
+}
 
-                    c.lvl50_stats.kick,
-                    c.lvl50_stats.control,
-                    c.lvl50_stats.technique,
-                    c.lvl50_stats.pressure,
-                    c.lvl50_stats.physical,
-                    c.lvl50_stats.agility,
-                    c.lvl50_stats.intelligence,
-
-                    c.lvl99_stats.kick,
-                    c.lvl99_stats.control,
-                    c.lvl99_stats.technique,
-                    c.lvl99_stats.pressure,
-                    c.lvl99_stats.physical,
-                    c.lvl99_stats.agility,
-                    c.lvl99_stats.intelligence,
-                ],
-            )?;
-        }
+/// Drops a table of an older version of the dataminer that has been
+/// replaced by a view of the same name.
+fn drop_legacy_table(database: &Connection, table: &str) -> Result<()> {
+    let is_table: bool = database.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = ?1", 
+        [table], 
+        |row| row.get(0)
+    )?;
+
+    if is_table {
+        database.execute(&format!("DROP TABLE {table};"), ())?;
     }
-    
-    tx.commit()
+
+    Ok(())
+}
+
+/// A view holding the characters matching `condition`, with their level 50
+/// and 99 stats as columns.
+fn rarity_view(name: &str, condition: &str) -> String {
+    let stat_columns = |level: u8| STAT_NAMES.iter()
+        .map(|stat| format!("lvl{level}.{stat} AS lvl{level}_{stat}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!(
+        "DROP VIEW IF EXISTS {name};
+        CREATE VIEW {name} AS
+            SELECT c.index_id, c.name_id, c.description_id, c.element, c.main_position, c.alt_position, c.style, c.series_id,
+                {}, 
+                {},
+                c.chara_base_id, c.chara_param_id, c.rarity, c.growth_pattern, c.chara_rank, c.portrait_path
+            FROM characters c
+            JOIN character_stats lvl50 ON lvl50.index_id = c.index_id AND lvl50.rarity = c.rarity AND lvl50.level = 50
+            JOIN character_stats lvl99 ON lvl99.index_id = c.index_id AND lvl99.rarity = c.rarity AND lvl99.level = {MAX_LEVEL}
+            WHERE c.{condition};",
+        stat_columns(50),
+        stat_columns(MAX_LEVEL),
+    )
 }
 
-fn insert_heroes(conn: &mut Connection, characters: &Vec<Character>) -> rusqlite::Result<()> {
+const STAT_NAMES: [&str; 7] = ["kick", "control", "technique", "pressure", "physical", "agility", "intelligence"];
+
+/// Inserts the characters, and their level 50 and 99 stats into `character_stats`.
+fn insert_characters(conn: &mut Connection, characters: &[&Character], portraits: &HashMap<i32, String>) -> rusqlite::Result<()> {
     let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Exclusive)?;
 
     {
-        let mut stmt = tx.prepare_cached(
-            "INSERT INTO heroes (
-                index_id, name_id, description_id, element, main_position, alt_position, style, series_id,
-                lvl50_kick, lvl50_control, lvl50_technique, lvl50_pressure,
-                lvl50_physical, lvl50_agility, lvl50_intelligence,
-                lvl99_kick, lvl99_control, lvl99_technique, lvl99_pressure,
-                lvl99_physical, lvl99_agility, lvl99_intelligence
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 
-                        ?9, ?10, ?11, ?12, ?13, ?14, ?15, 
-                        ?16, ?17, ?18, ?19, ?20, ?21, ?22)"
+        let mut character_stmt = tx.prepare_cached(
+            "INSERT INTO characters (
+                index_id, rarity, chara_base_id, chara_param_id, name_id, description_id,
+                element, main_position, alt_position, style, series_id, growth_pattern, chara_rank, portrait_path
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+            ON CONFLICT(index_id, rarity) DO UPDATE SET
+                chara_base_id = excluded.chara_base_id, chara_param_id = excluded.chara_param_id,
+                name_id = excluded.name_id, description_id = excluded.description_id,
+                element = excluded.element, main_position = excluded.main_position,
+                alt_position = excluded.alt_position, style = excluded.style, series_id = excluded.series_id,
+                growth_pattern = excluded.growth_pattern, chara_rank = excluded.chara_rank,
+                portrait_path = excluded.portrait_path"
+        )?;
+
+        let mut stats_stmt = tx.prepare_cached(
+            "INSERT INTO character_stats (
+                index_id, rarity, level, kick, control, technique, pressure, physical, agility, intelligence
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            ON CONFLICT(index_id, rarity, level) DO UPDATE SET
+                kick = excluded.kick, control = excluded.control, technique = excluded.technique,
+                pressure = excluded.pressure, physical = excluded.physical, agility = excluded.agility,
+                intelligence = excluded.intelligence"
         )?;
 
         for c in characters {
-            stmt.execute(
+            character_stmt.execute(
                 params![
                     c.index,
+                    c.rarity,
+                    c.chara_base_id,
+                    c.chara_param_id,
                     c.name_id,
                     c.description_id,
                     c.element as i32,
@@ -400,75 +525,48 @@ fn insert_heroes(conn: &mut Connection, characters: &Vec<Character>) -> rusqlite
                     c.alt_position as i32,
                     c.style as i32,
                     c.series_id,
-
-                    c.lvl50_stats.kick,
-                    c.lvl50_stats.control,
-                    c.lvl50_stats.technique,
-                    c.lvl50_stats.pressure,
-                    c.lvl50_stats.physical,
-                    c.lvl50_stats.agility,
-                    c.lvl50_stats.intelligence,
-
-                    c.lvl99_stats.kick,
-                    c.lvl99_stats.control,
-                    c.lvl99_stats.technique,
-                    c.lvl99_stats.pressure,
-                    c.lvl99_stats.physical,
-                    c.lvl99_stats.agility,
-                    c.lvl99_stats.intelligence,
+                    c.growth_pattern,
+                    c.chara_rank,
+                    portraits.get(&c.index),
                 ],
             )?;
+
+            for (level, stats) in [(50, &c.lvl50_stats), (MAX_LEVEL, &c.lvl99_stats)] {
+                stats_stmt.execute(
+                    params![
+                        c.index,
+                        c.rarity,
+                        level,
+                        stats.kick,
+                        stats.control,
+                        stats.technique,
+                        stats.pressure,
+                        stats.physical,
+                        stats.agility,
+                        stats.intelligence,
+                    ],
+                )?;
+            }
         }
     }
-    
+
     tx.commit()
 }
 
-fn insert_basaras(conn: &mut Connection, characters: &Vec<Character>) -> rusqlite::Result<()> {
+/// Each entry is (index_id, rarity, slot, skill_id), the slot being the
+/// position of the skill in the character's technique paths.
+fn insert_character_skills(conn: &mut Connection, skills: &[(i32, i32, i32, i32)]) -> rusqlite::Result<()> {
     let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Exclusive)?;
 
     {
         let mut stmt = tx.prepare_cached(
-            "INSERT INTO basaras (
-                index_id, name_id, description_id, element, main_position, alt_position, style, series_id,
-                lvl50_kick, lvl50_control, lvl50_technique, lvl50_pressure,
-                lvl50_physical, lvl50_agility, lvl50_intelligence,
-                lvl99_kick, lvl99_control, lvl99_technique, lvl99_pressure,
-                lvl99_physical, lvl99_agility, lvl99_intelligence
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 
-                        ?9, ?10, ?11, ?12, ?13, ?14, ?15, 
-                        ?16, ?17, ?18, ?19, ?20, ?21, ?22)"
+            "INSERT INTO character_skills (index_id, rarity, slot, skill_id) 
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(index_id, rarity, slot) DO UPDATE SET skill_id = excluded.skill_id"
         )?;
 
-        for c in characters {
-            stmt.execute(
-                params![
-                    c.index,
-                    c.name_id,
-                    c.description_id,
-                    c.element as i32,
-                    c.main_position as i32,
-                    c.alt_position as i32,
-                    c.style as i32,
-                    c.series_id,
-
-                    c.lvl50_stats.kick,
-                    c.lvl50_stats.control,
-                    c.lvl50_stats.technique,
-                    c.lvl50_stats.pressure,
-                    c.lvl50_stats.physical,
-                    c.lvl50_stats.agility,
-                    c.lvl50_stats.intelligence,
-
-                    c.lvl99_stats.kick,
-                    c.lvl99_stats.control,
-                    c.lvl99_stats.technique,
-                    c.lvl99_stats.pressure,
-                    c.lvl99_stats.physical,
-                    c.lvl99_stats.agility,
-                    c.lvl99_stats.intelligence,
-                ],
-            )?;
+        for (index, rarity, slot, skill_id) in skills {
+            stmt.execute(params![index, rarity, slot, skill_id])?;
         }
     }
     
@@ -1,4 +1,6 @@
-#[derive(Debug)]
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
 pub struct Character {
     pub index: i32,
     pub name_id: i32,
@@ -10,10 +12,18 @@ pub struct Character {
     pub lvl50_stats: Stats,
     pub lvl99_stats: Stats,
     pub series_id: i32,
-    
+    /// The ID of the character in chara_base, shared by all its rarities
+    pub chara_base_id: i32,
+    /// The ID of this rarity of the character in chara_param
+    pub chara_param_id: i32,
+    pub rarity: i32,
+    /// With the main position and rank, selects the growth table entry
+    pub growth_pattern: u8,
+    pub chara_rank: u8,
+    pub skills: Vec<i32>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 pub enum Element {
     WIND = 1,
     FOREST = 2,
@@ -22,6 +32,13 @@ pub enum Element {
     UNKNOWN = 5,
 }
 
+impl Element {
+    /// The value the game uses for this element, `UNKNOWN` having none
+    pub fn id(self) -> Option<i32> {
+        (self != Element::UNKNOWN).then_some(self as i32)
+    }
+}
+
 impl From<i32> for Element {
     fn from(value: i32) -> Self {
         match value {
@@ -35,7 +52,7 @@ impl From<i32> for Element {
 }
 
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 pub enum Position {
     GK = 1,
     DF = 4,
@@ -56,7 +73,7 @@ impl From<i32> for Position {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 pub enum Style {
     BREACH = 0,
     COUNTER = 1,
@@ -81,7 +98,7 @@ impl From<i32> for Style {
     }
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Serialize)]
 pub struct Stats {
     pub kick: u16,
     pub control: u16,
@@ -90,4 +107,54 @@ pub struct Stats {
     pub physical: u16,
     pub agility: u16,
     pub intelligence: u16,
-}
\ No newline at end of file
+}
+
+/// The highest level a character can reach
+pub const MAX_LEVEL: u8 = 99;
+
+/// The rarity of a character, as stored in chara_param
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum Rarity {
+    LEGENDARY = 0,
+    HERO = 5,
+    BASARA = 8,
+    UNKNOWN = 9,
+}
+
+impl From<i32> for Rarity {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => Rarity::LEGENDARY,
+            5..8 => Rarity::HERO, // Heroes go from rarity 5 to 7
+            8 => Rarity::BASARA,
+            _ => Rarity::UNKNOWN,
+        }
+    }
+}
+
+impl Rarity {
+    /// The name of the kind of character, as written in the changelogs
+    pub fn kind(self) -> &'static str {
+        match self {
+            Rarity::LEGENDARY => "character",
+            Rarity::HERO => "hero",
+            Rarity::BASARA => "basara",
+            Rarity::UNKNOWN => "unknown",
+        }
+    }
+}
+
+impl Stats {
+    /// Applies `f` to every stat
+    pub(crate) fn map(self, f: impl Fn(u16) -> u16) -> Stats {
+        Stats {
+            kick:           f(self.kick),
+            control:        f(self.control),
+            technique:      f(self.technique),
+            pressure:       f(self.pressure),
+            physical:       f(self.physical),
+            agility:        f(self.agility),
+            intelligence:   f(self.intelligence),
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::error::Error;
+
+use ievr_dataminer::settings::SettingsOverrides;
+
+pub const USAGE: &str = "Usage: ievr_dataminer [COMMAND] [OPTIONS]
+
+Commands:
+  extract   Extract the required game files with IEVR Toolbox
+  mine      Parse the already extracted game files into databases
+  check     Report the required game files that are missing
+  export    Extract the missing game files, then mine them (default)
+  diff      Compare two versions of the game data: diff <OLD> <NEW>, each being
+            an extraction folder, an output folder or a characters.sqlite file
+  dump      Write every table of game files into SQLite: dump <FILE|GLOB>..., the
+            globs being relative to the extraction folder
+  verify    Run integrity checks on the databases of the output folder, exiting
+            with an error when one fails
+
+Options:
+  --settings <PATH>           Settings file to read [default: settings.toml]
+  --output-folder <PATH>      Overrides datamining.output_folder
+  --extraction-folder <PATH>  Overrides datamining.extraction_folder
+  --game-folder <PATH>        Overrides extraction.game_folder
+  --threads <N|auto>          Overrides extraction.threads
+  --memory <N|auto>           Overrides extraction.memory
+  --output-format <LIST>      Overrides datamining.output_format, comma separated
+  --json-inline-names <BOOL>  Overrides datamining.json_inline_names
+  --incremental <BOOL>        Overrides datamining.incremental
+  --strict <BOOL>             Overrides datamining.strict, failing on the first anomaly in the game files
+  --languages <LIST>          Overrides datamining.languages, comma separated
+  --merged-text <BOOL>        Overrides datamining.merged_text
+  --portraits <BOOL>          Overrides datamining.portraits, experimental
+  --diff-output <PATH>        Folder where diff writes changelog.md and diff.json [default: .]
+  --dump-output <PATH>        Database written by dump [default: dump.sqlite]
+  -h, --help                  Print this help";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Extract,
+    Mine,
+    Check,
+    Export,
+    Diff,
+    Dump,
+    Verify,
+}
+
+#[derive(Debug)]
+pub struct Cli {
+    pub command: Command,
+    pub settings_path: String,
+    pub overrides: SettingsOverrides,
+    /// The positional arguments following the command
+    pub paths: Vec<String>,
+    pub diff_output: String,
+    pub dump_output: String,
+    pub help: bool,
+}
+
+impl Cli {
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Cli, CliError> {
+        let mut cli = Cli {
+            command: Command::Export,
+            settings_path: "settings.toml".to_owned(),
+            overrides: SettingsOverrides::default(),
+            paths: Vec::new(),
+            diff_output: ".".to_owned(),
+            dump_output: "dump.sqlite".to_owned(),
+            help: false,
+        };
+
+        let mut command = None;
+
+        while let Some(arg) = args.next() {
+            // We accept both "--flag value" and "--flag=value"
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if arg.starts_with("--") => (flag.to_owned(), Some(value.to_owned())),
+                _ => (arg.clone(), None),
+            };
+
+            let mut value = || match inline_value.clone().or_else(|| args.next()) {
+                Some(v) if !v.is_empty() => Ok(v),
+                _ => Err(CliError::MissingValue(flag.clone())),
+            };
+
+            match flag.as_str() {
+                "-h" | "--help" => cli.help = true,
+                "--settings" => cli.settings_path = value()?,
+                "--output-folder" => cli.overrides.output_folder = Some(value()?),
+                "--extraction-folder" => cli.overrides.extraction_folder = Some(value()?),
+                "--game-folder" => cli.overrides.game_folder = Some(value()?),
+                "--threads" => cli.overrides.threads = Some(parse_auto(&flag, value()?)?),
+                "--memory" => cli.overrides.memory = Some(parse_auto(&flag, value()?)?),
+                "--output-format" => {
+                    let formats = value()?;
+                    cli.overrides.output_formats = Some(formats.split(',')
+                        .map(|f| f.parse().map_err(|_| CliError::InvalidValue(flag.clone(), formats.clone())))
+                        .collect::<Result<_, _>>()?);
+                },
+                "--json-inline-names" => cli.overrides.json_inline_names = Some(parse_bool(&flag, value()?)?),
+                "--incremental" => cli.overrides.incremental = Some(parse_bool(&flag, value()?)?),
+                "--merged-text" => cli.overrides.merged_text = Some(parse_bool(&flag, value()?)?),
+                "--portraits" => cli.overrides.portraits = Some(parse_bool(&flag, value()?)?),
+                "--strict" => cli.overrides.strict = Some(parse_bool(&flag, value()?)?),
+                "--languages" => cli.overrides.languages = Some(value()?.split(',')
+                    .map(|language| language.trim().to_owned())
+                    .filter(|language| !language.is_empty())
+                    .collect()),
+                "--diff-output" => cli.diff_output = value()?,
+                "--dump-output" => cli.dump_output = value()?,
+                _ if flag.starts_with('-') => return Err(CliError::UnknownFlag(flag)),
+                _ if matches!(command, Some(Command::Diff | Command::Dump)) => cli.paths.push(flag),
+                _ if command.is_some() => return Err(CliError::UnexpectedArgument(flag)),
+                _ => command = Some(match flag.as_str() {
+                    "extract" => Command::Extract,
+                    "mine" => Command::Mine,
+                    "check" => Command::Check,
+                    "export" => Command::Export,
+                    "diff" => Command::Diff,
+                    "dump" => Command::Dump,
+                    "verify" => Command::Verify,
+                    _ => return Err(CliError::UnknownCommand(flag)),
+                }),
+            }
+        }
+
+        if let Some(command) = command {
+            cli.command = command;
+        }
+
+        if cli.command == Command::Diff && cli.paths.len() != 2 && !cli.help {
+            return Err(CliError::DiffPaths);
+        }
+
+        if cli.command == Command::Dump && cli.paths.is_empty() && !cli.help {
+            return Err(CliError::DumpPaths);
+        }
+
+        Ok(cli)
+    }
+}
+
+/// Parses a value that can either be a number or "auto", the latter being `None`.
+fn parse_auto<T: std::str::FromStr>(flag: &str, value: String) -> Result<Option<T>, CliError> {
+    if value == "auto" {
+        return Ok(None);
+    }
+
+    value.parse()
+        .map(Some)
+        .map_err(|_| CliError::InvalidValue(flag.to_owned(), value))
+}
+
+/// Parses a value that must be "true" or "false".
+fn parse_bool(flag: &str, value: String) -> Result<bool, CliError> {
+    value.parse().map_err(|_| CliError::InvalidValue(flag.to_owned(), value))
+}
+
+#[derive(Debug)]
+pub enum CliError {
+    UnknownCommand(String),
+    UnknownFlag(String),
+    UnexpectedArgument(String),
+    MissingValue(String),
+    InvalidValue(String, String),
+    DiffPaths,
+    DumpPaths,
+}
+
+impl Error for CliError {}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownCommand(c) => write!(f, "Unknown command \"{c}\""),
+            Self::UnknownFlag(flag) => write!(f, "Unknown option \"{flag}\""),
+            Self::UnexpectedArgument(a) => write!(f, "Unexpected argument \"{a}\""),
+            Self::MissingValue(flag) => write!(f, "Missing value for \"{flag}\""),
+            Self::InvalidValue(flag, v) => write!(f, "Invalid value \"{v}\" for \"{flag}\""),
+            Self::DiffPaths => write!(f, "The diff command needs exactly two paths to compare"),
+            Self::DumpPaths => write!(f, "The dump command needs at least one file or glob"),
+        }
+    }
+}
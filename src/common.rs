@@ -1,40 +1,232 @@
 use std::{fs::File, path::Path};
 
-use ievr_cfg_bin_editor_core::{Database, Value, parse_database};
+use ievr_cfg_bin_editor_core::{Database, Row, Value, parse_database};
 use memmap2::Mmap;
 
-pub fn parse_gamefile(file_path: &Path) -> Option<Database> {
-    let file = File::open(file_path).unwrap();
+use crate::{
+    file_operations::parse_file_version,
+    schema::{ColumnSchema, TableSchema, ValueType},
+    validation::{MiningError, MiningErrorKind, ValidationReport},
+};
 
-    let mmap = unsafe { Mmap::map(&file).unwrap() };
+pub fn parse_gamefile(file_path: &Path) -> Result<Database, MiningError> {
+    let file_name = file_path.file_name().unwrap_or_default().to_string_lossy();
+    let unreadable = |reason: String| MiningError::new(&file_name, MiningErrorKind::UnreadableFile { reason });
 
-    parse_database(&mmap).ok()
+    let file = File::open(file_path).map_err(|e| unreadable(e.to_string()))?;
+
+    let mmap = unsafe { Mmap::map(&file).map_err(|e| unreadable(e.to_string()))? };
+
+    parse_database(&mmap).map_err(|_| unreadable("invalid cfg.bin file".to_owned()))
+}
+
+/// A parsed game file, which remembers its name to locate the anomalies found in it.
+pub struct GameFile {
+    name: String,
+    database: Database,
+}
+
+impl GameFile {
+    pub fn open(file_path: &Path) -> Result<GameFile, MiningError> {
+        Ok(GameFile {
+            name: file_path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+            database: parse_gamefile(file_path)?,
+        })
+    }
+
+    /// Whether the file holds a table, for the tables only some files have.
+    pub fn has_table(&self, schema: &TableSchema) -> bool {
+        self.database.table(schema.name).is_some()
+    }
+
+    /// Opens a table, checking that its columns match the layout expected for the
+    /// version of the file. A mismatch means the game changed the layout of the table,
+    /// so none of its rows can be trusted: it is recorded for the whole table, which
+    /// is then read as empty, or returned in strict mode.
+    pub fn table(&self, schema: &'static TableSchema, report: &mut ValidationReport) -> Result<GameTable<'_>, MiningError> {
+        let Some(table) = self.database.table(schema.name) else {
+            return Err(MiningError {
+                table: Some(schema.name),
+                ..MiningError::new(&self.name, MiningErrorKind::MissingTable)
+            });
+        };
+
+        let mut game_table = GameTable {
+            file: &self.name,
+            name: schema.name,
+            columns: schema.columns(parse_file_version(&self.name)),
+            rows: &table.rows()[..],
+        };
+
+        // We check the first row, the later ones being checked as they are read
+        if let Some(row) = game_table.rows().next()
+            && let Err(anomaly) = row.check_schema()
+        {
+            report.push(MiningError { row: None, ..anomaly })?;
+            game_table.rows = &[];
+        }
+
+        Ok(game_table)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct GameTable<'a> {
+    file: &'a str,
+    name: &'static str,
+    columns: &'static [ColumnSchema],
+    rows: &'a [Row],
 }
 
-pub fn parse_int_value(value: &Value) -> i32 {
+impl<'a> GameTable<'a> {
+    pub fn rows(&self) -> impl Iterator<Item = GameRow<'a>> + use<'a> {
+        let (file, table, columns) = (self.file, self.name, self.columns);
+
+        self.rows.iter()
+            .enumerate()
+            .map(move |(index, row)| GameRow { file, table, columns, index, row })
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A row of a game table, whose values are read by column name along with
+/// the location of any anomaly, instead of panicking on unexpected data.
+#[derive(Clone, Copy)]
+pub struct GameRow<'a> {
+    file: &'a str,
+    table: &'static str,
+    columns: &'static [ColumnSchema],
+    index: usize,
+    row: &'a Row,
+}
+
+impl<'a> GameRow<'a> {
+    pub fn error(&self, column: Option<&str>, kind: MiningErrorKind) -> MiningError {
+        let column = column.and_then(|name| self.columns.iter().find(|column| column.name == name));
+
+        MiningError {
+            table: Some(self.table),
+            row: Some(self.index),
+            column: column.map(|column| column.index),
+            column_name: column.map(|column| column.name),
+            ..MiningError::new(self.file, kind)
+        }
+    }
+
+    fn column(&self, name: &str) -> Result<&'static ColumnSchema, MiningError> {
+        self.columns.iter()
+            .find(|column| column.name == name)
+            .ok_or_else(|| self.error(None, MiningErrorKind::UnknownColumn { name: name.to_owned() }))
+    }
+
+    /// Checks that the row has every column of the schema, with values of the expected type
+    fn check_schema(&self) -> Result<(), MiningError> {
+        for column in self.columns {
+            let values = self.row.values.get(column.index)
+                .ok_or_else(|| self.error(Some(column.name), MiningErrorKind::MissingColumn))?;
+
+            if let Some(value) = values.iter().find(|value| ValueType::of(value) != column.value_type) {
+                return Err(self.error(Some(column.name), MiningErrorKind::SchemaMismatch {
+                    expected: column.value_type,
+                    found: ValueType::of(value),
+                }));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Every value of a column, as columns can hold arrays
+    pub fn values(&self, name: &str) -> Result<&'a [Value], MiningError> {
+        let column = self.column(name)?;
+
+        self.row.values.get(column.index)
+            .map(|values| values.as_slice())
+            .ok_or_else(|| self.error(Some(column.name), MiningErrorKind::MissingColumn))
+    }
+
+    fn value<T>(&self, name: &str, parse: fn(&Value) -> Result<T, MiningErrorKind>) -> Result<T, MiningError> {
+        let column = self.column(name)?;
+        let value = self.values(name)?.first()
+            .ok_or_else(|| self.error(Some(column.name), MiningErrorKind::MissingColumn))?;
+
+        parse(value).map_err(|kind| self.error(Some(column.name), kind))
+    }
+
+    pub fn int(&self, name: &str) -> Result<i32, MiningError> {
+        self.value(name, parse_int_value)
+    }
+
+    pub fn uint(&self, name: &str) -> Result<u32, MiningError> {
+        self.value(name, parse_uint_value)
+    }
+
+    pub fn byte(&self, name: &str) -> Result<u8, MiningError> {
+        self.value(name, parse_byte_value)
+    }
+
+    /// An integer stored by the game as an `Int`, that has to fit in a `u16`
+    pub fn u16(&self, name: &str) -> Result<u16, MiningError> {
+        let value = self.int(name)?;
+
+        u16::try_from(value).map_err(|_| self.error(Some(name), MiningErrorKind::OutOfRange { value }))
+    }
+
+    pub fn string(&self, name: &str) -> Result<String, MiningError> {
+        self.value(name, parse_string_value)
+    }
+
+    /// Every value of several columns, parsed as integers
+    pub fn ints(&self, names: &[&str]) -> Result<Vec<i32>, MiningError> {
+        let mut ints = Vec::new();
+
+        for name in names {
+            let column = self.column(name)?;
+
+            for value in self.values(name)? {
+                ints.push(parse_int_value(value).map_err(|kind| self.error(Some(column.name), kind))?);
+            }
+        }
+
+        Ok(ints)
+    }
+}
+
+fn unexpected_type(expected: ValueType, value: &Value) -> MiningErrorKind {
+    MiningErrorKind::UnexpectedType { expected, found: ValueType::of(value) }
+}
+
+pub fn parse_int_value(value: &Value) -> Result<i32, MiningErrorKind> {
     match value {
-        Value::Int(v) => *v,
-        _ => unreachable!()
+        Value::Int(v) => Ok(*v),
+        _ => Err(unexpected_type(ValueType::Int, value))
     }
 }
 
-pub fn parse_uint_value(value: &Value) -> u32 {
+pub fn parse_uint_value(value: &Value) -> Result<u32, MiningErrorKind> {
     match value {
-        Value::UInt(v) => *v,
-        _ => unreachable!()
+        Value::UInt(v) => Ok(*v),
+        _ => Err(unexpected_type(ValueType::UInt, value))
     }
 }
 
-pub fn parse_byte_value(value: &Value) -> u8 {
+pub fn parse_byte_value(value: &Value) -> Result<u8, MiningErrorKind> {
     match value {
-        Value::Byte(v) => *v,
-        _ => unreachable!()
+        Value::Byte(v) => Ok(*v),
+        _ => Err(unexpected_type(ValueType::Byte, value))
     }
 }
 
-pub fn parse_string_value(value: &Value) -> String {
+pub fn parse_string_value(value: &Value) -> Result<String, MiningErrorKind> {
     match value {
-        Value::String(s) => s.clone(),
-        _ => panic!("Encountered {:?}", value)
+        Value::String(s) => Ok(s.clone()),
+        _ => Err(unexpected_type(ValueType::String, value))
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,417 @@
+use std::{collections::{BTreeMap, HashMap}, error::Error, fmt::Write, path::Path};
+
+use regex::Regex;
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    CHARA_REQUIRED_FILES, CHARA_ROOT_PATH, Character, DATABASES, TEXT_DATABASES_ROOT, TEXT_ROOT_PATH,
+    characters::{CharacterFiles, Element, Position, Rarity, Stats, Style, load_character_data},
+    file_operations::parse_file_version,
+    text::{LocalizedText, TextFiles},
+    validation::ValidationReport,
+};
+
+/// The language used for the names in the changelog
+const CHANGELOG_LANGUAGE: &str = "en";
+
+/// The number of technique path slots of a character, the size of `Character::skills`
+const SKILL_SLOTS: usize = 6;
+
+/// One side of a comparison, loaded either from an extraction
+/// folder or from databases produced by a previous run.
+pub struct DiffSource {
+    pub characters: Vec<Character>,
+    /// The game version, when it could be found
+    pub version: Option<String>,
+    /// The character names in the changelog language, by name ID
+    pub names: HashMap<i32, String>,
+    /// The character fields this side does not know, which are not compared
+    pub missing_fields: Vec<&'static str>,
+}
+
+impl DiffSource {
+    /// `path` can be an extraction folder, an output folder or a `characters.sqlite` file.
+    pub fn load(path: &Path) -> Result<DiffSource, Box<dyn Error>> {
+        if path.is_file() {
+            let text_database = path.with_file_name(TEXT_DATABASES_ROOT).join(format!("{CHANGELOG_LANGUAGE}.sqlite"));
+            Self::from_databases(path, &text_database)
+        } else if path.join(DATABASES[0]).exists() {
+            let text_database = path.join(TEXT_DATABASES_ROOT).join(format!("{CHANGELOG_LANGUAGE}.sqlite"));
+            Self::from_databases(&path.join(DATABASES[0]), &text_database)
+        } else {
+            Self::from_extraction(path)
+        }
+    }
+
+    fn from_extraction(extraction_root: &Path) -> Result<DiffSource, Box<dyn Error>> {
+        let chara_files = CharacterFiles::resolve(extraction_root)?;
+
+        let version = parse_file_version(&chara_files.chara_param)
+            .map(|v| v.map(|n| n.to_string()).join("."));
+
+        // The anomalies only matter when mining, the valid characters are compared anyway
+        let characters = load_character_data(extraction_root, &chara_files, &mut ValidationReport::default())?.characters;
+
+        // The names are a nice to have, we do not fail without them
+        let names = TextFiles::resolve(extraction_root, CHANGELOG_LANGUAGE).ok()
+            .and_then(|files| LocalizedText::load(&extraction_root.join(TEXT_ROOT_PATH).join(CHANGELOG_LANGUAGE), &files, &mut ValidationReport::default()).ok())
+            .map(|text| text.chara_names)
+            .unwrap_or_default();
+
+        Ok(DiffSource { characters, version, names, missing_fields: Vec::new() })
+    }
+
+    fn from_databases(character_database: &Path, text_database: &Path) -> Result<DiffSource, Box<dyn Error>> {
+        let conn = Connection::open_with_flags(character_database, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+
+        let mut characters = Vec::new();
+
+        let mut missing_fields = Vec::new();
+
+        let columns: Vec<String> = conn.prepare("PRAGMA table_info(characters)")?
+            .query_map([], |row| row.get(1))?
+            .collect::<rusqlite::Result<_>>()?;
+
+        // Older databases do not store the rarity, so every hero is read with the
+        // lowest hero rarity and only matched by index, nor the game IDs and growth keys
+        let legacy = !columns.iter().any(|column| column == "rarity");
+        if legacy {
+            missing_fields.extend(["rarity", "chara_base_id", "chara_param_id", "growth_pattern", "chara_rank"]);
+        }
+
+        // The characters of every rarity are now in a single table, with views
+        // keeping the layout of the former tables
+        let has_views: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'view' AND name = 'legendaries'",
+            [],
+            |row| row.get(0)
+        )?;
+        let first_table = if has_views { "legendaries" } else { "characters" };
+
+        for (table, rarity) in [(first_table, 0), ("heroes", 5), ("basaras", 8)] {
+            let extra_columns = if legacy {
+                format!("0, 0, {rarity}, 0, 0")
+            } else {
+                "chara_base_id, chara_param_id, rarity, growth_pattern, chara_rank".to_owned()
+            };
+
+            let mut stmt = conn.prepare(&format!(
+                "SELECT index_id, name_id, description_id, element, main_position, alt_position, style, series_id,
+                    lvl50_kick, lvl50_control, lvl50_technique, lvl50_pressure, lvl50_physical, lvl50_agility, lvl50_intelligence,
+                    lvl99_kick, lvl99_control, lvl99_technique, lvl99_pressure, lvl99_physical, lvl99_agility, lvl99_intelligence,
+                    {extra_columns}
+                FROM {table}"
+            ))?;
+
+            let rows = stmt.query_map([], |row| {
+                let stats = |offset: usize| -> rusqlite::Result<Stats> {
+                    Ok(Stats {
+                        kick:           row.get(offset)?,
+                        control:        row.get(offset + 1)?,
+                        technique:      row.get(offset + 2)?,
+                        pressure:       row.get(offset + 3)?,
+                        physical:       row.get(offset + 4)?,
+                        agility:        row.get(offset + 5)?,
+                        intelligence:   row.get(offset + 6)?,
+                    })
+                };
+
+                Ok(Character {
+                    index:          row.get(0)?,
+                    name_id:        row.get(1)?,
+                    description_id: row.get(2)?,
+                    element:        Element::from(row.get::<_, i32>(3)?),
+                    main_position:  Position::from(row.get::<_, i32>(4)?),
+                    alt_position:   Position::from(row.get::<_, i32>(5)?),
+                    style:          Style::from(row.get::<_, i32>(6)?),
+                    series_id:      row.get(7)?,
+                    lvl50_stats:    stats(8)?,
+                    lvl99_stats:    stats(15)?,
+                    chara_base_id:  row.get(22)?,
+                    chara_param_id: row.get(23)?,
+                    rarity:         row.get(24)?,
+                    growth_pattern: row.get(25)?,
+                    chara_rank:     row.get(26)?,
+                    // Filled from character_skills below
+                    skills:         Vec::new(),
+                })
+            })?;
+
+            for character in rows {
+                characters.push(character?);
+            }
+        }
+
+        // The databases written before the skills were extracted do not have their table
+        let has_skills: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'character_skills'",
+            [],
+            |row| row.get(0)
+        )?;
+
+        if has_skills {
+            let mut skills: HashMap<(i32, i32), Vec<i32>> = HashMap::new();
+            let mut stmt = conn.prepare("SELECT index_id, rarity, slot, skill_id FROM character_skills")?;
+
+            for row in stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get::<_, u32>(2)?, row.get(3)?)))? {
+                let (index, rarity, slot, skill_id) = row?;
+                let slot = slot as usize;
+
+                // Only the learned skills are stored, the empty slots holding 0 like in the game files
+                let slots = skills.entry((index, rarity)).or_insert_with(|| vec![0; SKILL_SLOTS]);
+                if slot >= slots.len() {
+                    slots.resize(slot + 1, 0);
+                }
+                slots[slot] = skill_id;
+            }
+
+            for character in &mut characters {
+                character.skills = skills.remove(&(character.index, character.rarity)).unwrap_or_else(|| vec![0; SKILL_SLOTS]);
+            }
+        } else {
+            missing_fields.push("skills");
+        }
+
+        let mut names = HashMap::new();
+
+        if text_database.exists() {
+            let text_conn = Connection::open_with_flags(text_database, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+            let mut stmt = text_conn.prepare("SELECT id, name FROM character_names")?;
+
+            for row in stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))? {
+                let (id, name) = row?;
+                names.insert(id, name);
+            }
+        }
+
+        // The databases record the version of the game files they were built from,
+        // except the older ones which do not have a metadata table
+        let version = chara_param_version(&conn).ok().flatten();
+
+        Ok(DiffSource { characters, version, names, missing_fields })
+    }
+}
+
+/// The differences between two versions of the game data.
+#[derive(Debug, Serialize)]
+pub struct GameDiff {
+    pub old_version: Option<String>,
+    pub new_version: Option<String>,
+    pub added: Vec<CharacterEntry>,
+    pub removed: Vec<CharacterEntry>,
+    pub changed: Vec<CharacterChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CharacterEntry {
+    pub index: i32,
+    pub rarity: i32,
+    pub kind: &'static str,
+    pub name_id: i32,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CharacterChange {
+    #[serde(flatten)]
+    pub character: CharacterEntry,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+/// Returns the version of the chara_param file listed in the metadata table.
+fn chara_param_version(conn: &Connection) -> rusqlite::Result<Option<String>> {
+    let chara_param = Regex::new(CHARA_REQUIRED_FILES["chara_param"]).unwrap();
+
+    let mut stmt = conn.prepare("SELECT source_file, version FROM metadata")?;
+    let sources = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)))?;
+
+    for source in sources {
+        let (source_file, version) = source?;
+
+        if source_file.strip_prefix(CHARA_ROOT_PATH).is_some_and(|file_name| chara_param.is_match(file_name)) {
+            return Ok(version);
+        }
+    }
+
+    Ok(None)
+}
+
+impl GameDiff {
+    pub fn compute(old: &DiffSource, new: &DiffSource) -> GameDiff {
+        // Without the rarity of one side, the heroes can only be matched by index
+        let by_kind = old.missing_fields.contains(&"rarity") || new.missing_fields.contains(&"rarity");
+
+        let old_characters = index_characters(&old.characters, by_kind);
+        let new_characters = index_characters(&new.characters, by_kind);
+
+        let entry = |character: &Character, source: &DiffSource| CharacterEntry {
+            index: character.index,
+            rarity: character.rarity,
+            kind: Rarity::from(character.rarity).kind(),
+            name_id: character.name_id,
+            name: new.names.get(&character.name_id).or(source.names.get(&character.name_id)).cloned(),
+        };
+
+        let added = new_characters.iter()
+            .filter(|(key, _)| !old_characters.contains_key(key))
+            .map(|(_, character)| entry(character, new))
+            .collect();
+
+        let removed = old_characters.iter()
+            .filter(|(key, _)| !new_characters.contains_key(key))
+            .map(|(_, character)| entry(character, old))
+            .collect();
+
+        let mut changed = Vec::new();
+
+        for (key, new_character) in new_characters.iter() {
+            let Some(old_character) = old_characters.get(key) else { continue };
+
+            let old_fields = flatten_fields(old_character);
+            let new_fields = flatten_fields(new_character);
+
+            let changes: Vec<FieldChange> = new_fields.into_iter()
+                .filter_map(|(field, new_value)| {
+                    if old.missing_fields.contains(&field.as_str()) || new.missing_fields.contains(&field.as_str()) {
+                        return None;
+                    }
+
+                    let old_value = old_fields.get(&field).cloned().unwrap_or(Value::Null);
+
+                    (old_value != new_value).then_some(FieldChange { field, old: old_value, new: new_value })
+                })
+                .collect();
+
+            if !changes.is_empty() {
+                changed.push(CharacterChange { character: entry(new_character, new), changes });
+            }
+        }
+
+        GameDiff {
+            old_version: old.version.clone(),
+            new_version: new.version.clone(),
+            added,
+            removed,
+            changed
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+
+        let version = |v: &Option<String>| v.clone().unwrap_or("unknown version".to_owned());
+        let _ = writeln!(markdown, "# Changelog: {} to {}\n", version(&self.old_version), version(&self.new_version));
+
+        if self.is_empty() {
+            markdown.push_str("No character changed.\n");
+            return markdown;
+        }
+
+        for (kind, title) in [("character", "New characters"), ("hero", "New heroes"), ("basara", "New basaras")] {
+            write_entries(&mut markdown, title, self.added.iter().filter(|e| e.kind == kind));
+        }
+
+        write_entries(&mut markdown, "Removed", self.removed.iter());
+
+        if !self.changed.is_empty() {
+            let _ = writeln!(markdown, "## Changed ({})\n", self.changed.len());
+
+            for change in self.changed.iter() {
+                let _ = writeln!(markdown, "### {} ({})\n", entry_title(&change.character), change.character.kind);
+                markdown.push_str("| Field | Old | New |\n|---|---|---|\n");
+
+                for field in change.changes.iter() {
+                    let _ = writeln!(markdown, "| {} | {} | {} |", field.field, cell(&field.old), cell(&field.new));
+                }
+
+                markdown.push('\n');
+            }
+        }
+
+        markdown
+    }
+}
+
+fn write_entries<'a>(markdown: &mut String, title: &str, entries: impl Iterator<Item = &'a CharacterEntry>) {
+    let entries: Vec<&CharacterEntry> = entries.collect();
+
+    if entries.is_empty() {
+        return;
+    }
+
+    let _ = writeln!(markdown, "## {title} ({})\n", entries.len());
+
+    for entry in entries {
+        let _ = writeln!(markdown, "- {} ({})", entry_title(entry), entry.kind);
+    }
+
+    markdown.push('\n');
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn entry_title(entry: &CharacterEntry) -> String {
+    match &entry.name {
+        Some(name) => format!("#{} {name}", entry.index),
+        None => format!("#{} (name {})", entry.index, entry.name_id),
+    }
+}
+
+/// Characters are identified by their index and rarity, every rarity
+/// of a character sharing the same index. With `by_kind`, the heroes of
+/// every rarity share the same key.
+fn index_characters(characters: &[Character], by_kind: bool) -> BTreeMap<(i32, i32), &Character> {
+    characters.iter()
+        .map(|c| match by_kind {
+            true => ((c.index, Rarity::from(c.rarity) as i32), c),
+            false => ((c.index, c.rarity), c),
+        })
+        .collect()
+}
+
+/// Lists the compared fields of a character, with the stats
+/// flattened to `lvl50_stats.kick`, `lvl99_stats.agility`...
+fn flatten_fields(character: &Character) -> BTreeMap<String, Value> {
+    let mut fields = BTreeMap::new();
+
+    if let Ok(Value::Object(object)) = serde_json::to_value(character) {
+        for (key, value) in object {
+            match value {
+                Value::Object(nested) => {
+                    for (nested_key, nested_value) in nested {
+                        fields.insert(format!("{key}.{nested_key}"), nested_value);
+                    }
+                }
+                value => { fields.insert(key, value); }
+            }
+        }
+    }
+
+    // These identify the character
+    fields.remove("index");
+    fields.remove("rarity");
+
+    fields
+}
@@ -0,0 +1,288 @@
+use std::{collections::HashSet, error::Error, fs, io, path::{Path, PathBuf}};
+
+use ievr_cfg_bin_editor_core::{Table, Value};
+use regex::Regex;
+use rusqlite::{Connection, TransactionBehavior, params, params_from_iter, types::Value as SqlValue};
+
+use crate::{common::parse_gamefile, schema::ValueType};
+
+/// The longest arrays that are split into one SQL column per value,
+/// the longer ones being stored as JSON arrays in a single column
+const MAX_EXPANDED_VALUES: usize = 16;
+
+/// What was written by a dump.
+#[derive(Debug, Default)]
+pub struct DumpSummary {
+    pub files: usize,
+    pub tables: usize,
+    pub rows: usize,
+}
+
+/// Returns the game files matching `pattern`, which is either the path of a
+/// file or a glob relative to the extraction folder (`**/chara_*.cfg.bin`).
+pub fn find_game_files(extraction_root: &Path, pattern: &str) -> io::Result<Vec<PathBuf>> {
+    if Path::new(pattern).is_file() {
+        return Ok(vec![PathBuf::from(pattern)]);
+    }
+
+    let regex = glob_regex(pattern);
+
+    let mut files = Vec::new();
+    find_files(extraction_root, extraction_root, &regex, &mut files)?;
+
+    // We sort the files so that the SQL table names do not depend on the file system
+    files.sort();
+
+    Ok(files)
+}
+
+fn find_files(root: &Path, folder: &Path, regex: &Regex, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(folder)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            find_files(root, &path, regex, files)?;
+            continue;
+        }
+
+        // Globs always use forward slashes, even on Windows
+        let relative_path = path.strip_prefix(root).unwrap_or(&path)
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        if regex.is_match(&relative_path) {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Translates a glob into a regex: `**` matches any number of folders,
+/// `*` anything but a folder separator and `?` a single character.
+fn glob_regex(pattern: &str) -> Regex {
+    let mut regex = String::from("^");
+    let mut chars = pattern.trim_start_matches("./").chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            },
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '\\' => regex.push('/'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    regex.push('$');
+
+    Regex::new(&regex).expect("escaped globs are valid regexes")
+}
+
+/// Writes every table of the game files into a new SQLite database at `output`,
+/// one SQL table per game table. The game tables are listed in `dump_tables`.
+pub fn dump_game_files(files: &[PathBuf], output: &Path) -> Result<DumpSummary, Box<dyn Error>> {
+    if fs::exists(output)? {
+        fs::remove_file(output)?;
+    }
+
+    let mut conn = Connection::open(output)?;
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Exclusive)?;
+
+    tx.execute(
+        "CREATE TABLE dump_tables (
+            sql_table TEXT PRIMARY KEY,
+            file TEXT NOT NULL,
+            game_table TEXT NOT NULL,
+            row_count INTEGER NOT NULL
+        )",
+        []
+    )?;
+
+    let mut summary = DumpSummary::default();
+    // The game tables cannot take the name of the table listing them
+    let mut sql_tables = HashSet::from(["dump_tables".to_owned()]);
+
+    for file in files {
+        let file_name = file.file_name().unwrap_or_default().to_string_lossy();
+
+        let database = match parse_gamefile(file) {
+            Ok(database) => database,
+            Err(e) => {
+                // We keep going, a glob can easily match files of another format
+                println!("[DUMP]: skipping {e}");
+                continue;
+            },
+        };
+
+        for table in database.tables() {
+            // The table names are only prefixed when they could collide with the ones of another file
+            let name = match files.len() {
+                1 => table.name().to_owned(),
+                _ => format!("{}__{}", file_name.trim_end_matches(".cfg.bin"), table.name()),
+            };
+            let sql_table = unique_name(&sanitize(&name), &mut sql_tables);
+
+            dump_table(&tx, &sql_table, table)?;
+
+            tx.execute(
+                "INSERT INTO dump_tables (sql_table, file, game_table, row_count) VALUES (?1, ?2, ?3, ?4)",
+                params![sql_table, file.to_string_lossy(), table.name(), table.rows().len() as i64]
+            )?;
+
+            summary.tables += 1;
+            summary.rows += table.rows().len();
+        }
+
+        summary.files += 1;
+    }
+
+    tx.commit()?;
+
+    Ok(summary)
+}
+
+/// How the values of a game column are stored in SQL.
+enum DumpColumn {
+    /// The value at `position` in the column, arrays being split into one SQL column per value
+    Value { index: usize, position: usize },
+    /// Every value of the column, as a JSON array
+    Array { index: usize },
+}
+
+fn dump_table(tx: &Connection, sql_table: &str, table: &Table) -> rusqlite::Result<()> {
+    let column_count = table.rows().iter().map(|row| row.values.len()).max().unwrap_or(0);
+
+    let mut columns = Vec::new();
+    let mut definitions = vec!["\"row_index\" INTEGER PRIMARY KEY".to_owned()];
+
+    for index in 0..column_count {
+        let cells = || table.rows().iter().filter_map(move |row| row.values.get(index));
+
+        let width = cells().map(Vec::len).max().unwrap_or(0);
+
+        // The columns are only typed when every value has the same type
+        let mut types = cells().flatten().map(ValueType::of);
+        let sql_type = match types.next() {
+            Some(first) if types.all(|value_type| value_type == first) => sql_type(first),
+            _ => "",
+        };
+        let definition = |name: String| match sql_type {
+            "" => format!("\"{name}\""),
+            sql_type => format!("\"{name}\" {sql_type}"),
+        };
+
+        match width {
+            0 | 1 => {
+                columns.push(DumpColumn::Value { index, position: 0 });
+                definitions.push(definition(format!("col{index}")));
+            },
+            2..=MAX_EXPANDED_VALUES => for position in 0..width {
+                columns.push(DumpColumn::Value { index, position });
+                definitions.push(definition(format!("col{index}_{position}")));
+            },
+            _ => {
+                columns.push(DumpColumn::Array { index });
+                definitions.push(format!("\"col{index}\" TEXT"));
+            },
+        }
+    }
+
+    tx.execute(&format!("CREATE TABLE \"{sql_table}\" ({})", definitions.join(", ")), [])?;
+
+    let placeholders = vec!["?"; columns.len() + 1].join(", ");
+    let mut stmt = tx.prepare(&format!("INSERT INTO \"{sql_table}\" VALUES ({placeholders})"))?;
+
+    for (row_index, row) in table.rows().iter().enumerate() {
+        let mut values = Vec::with_capacity(columns.len() + 1);
+        values.push(SqlValue::Integer(row_index as i64));
+
+        for column in &columns {
+            values.push(match *column {
+                DumpColumn::Value { index, position } => row.values.get(index)
+                    .and_then(|cell| cell.get(position))
+                    .map(sql_value)
+                    .unwrap_or(SqlValue::Null),
+                DumpColumn::Array { index } => match row.values.get(index) {
+                    Some(cell) => SqlValue::Text(json_array(cell)),
+                    None => SqlValue::Null,
+                },
+            });
+        }
+
+        stmt.execute(params_from_iter(values))?;
+    }
+
+    Ok(())
+}
+
+fn sql_type(value_type: ValueType) -> &'static str {
+    match value_type {
+        ValueType::Byte | ValueType::Short | ValueType::Int | ValueType::UInt => "INTEGER",
+        ValueType::Float => "REAL",
+        ValueType::String => "TEXT",
+    }
+}
+
+fn sql_value(value: &Value) -> SqlValue {
+    match value {
+        Value::Byte(v) => SqlValue::Integer(*v as i64),
+        Value::Short(v) => SqlValue::Integer(*v as i64),
+        Value::Int(v) => SqlValue::Integer(*v as i64),
+        Value::UInt(v) => SqlValue::Integer(*v as i64),
+        Value::Float(v) => SqlValue::Real(*v as f64),
+        Value::String(s) => SqlValue::Text(s.clone()),
+    }
+}
+
+fn json_array(values: &[Value]) -> String {
+    let values: Vec<serde_json::Value> = values.iter()
+        .map(|value| match value {
+            Value::Byte(v) => (*v).into(),
+            Value::Short(v) => (*v).into(),
+            Value::Int(v) => (*v).into(),
+            Value::UInt(v) => (*v).into(),
+            Value::Float(v) => (*v).into(),
+            Value::String(s) => s.as_str().into(),
+        })
+        .collect();
+
+    serde_json::Value::Array(values).to_string()
+}
+
+/// Keeps the characters that can be used in an SQL identifier without quoting,
+/// and prefixes the names SQLite reserves for its own tables.
+fn sanitize(name: &str) -> String {
+    let name: String = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' })
+        .collect();
+
+    match name.to_lowercase().starts_with("sqlite_") {
+        true => format!("game_{name}"),
+        false => name,
+    }
+}
+
+/// Appends a number to the names already used, as sanitizing can make them collide.
+fn unique_name(name: &str, used: &mut HashSet<String>) -> String {
+    let mut unique = name.to_owned();
+    let mut suffix = 2;
+
+    while !used.insert(unique.to_lowercase()) {
+        unique = format!("{name}_{suffix}");
+        suffix += 1;
+    }
+
+    unique
+}
@@ -1,107 +1,444 @@
-use std::{collections::HashMap, fs::{self, File}, path::Path};
+use std::{collections::{BTreeMap, HashMap}, error::Error, ffi::OsStr, fs::{self, File}, io, path::{Path, PathBuf}};
+
+use regex::Regex;
+use serde::Serialize;
 
 use crate::{
-    CHARA_REQUIRED_FILES, CHARA_ROOT_PATH, DATABASES, TEXT_DATABASES_ROOT, TEXT_REQUIRED_FILES, TEXT_ROOT_PATH, text::TEXT_LANGUAGES
+    DATABASES, TEXT_DATABASES_ROOT, characters::CharacterFiles, output::OutputFormat, skills::SkillFiles, text::TextFiles
 };
 
-pub fn create_required_files(output_folder: &Path) {
-    if let Ok(true) = fs::exists(output_folder) {
-        fs::remove_dir_all(output_folder).unwrap();
+/// Creates the output database files that do not exist yet in `output_folder`,
+/// with a text database for each of the `languages`.
+pub fn create_required_files(output_folder: &Path, output_formats: &[OutputFormat], languages: &[String]) -> io::Result<()> {
+    fs::create_dir_all(output_folder)?;
+
+    // The other formats create their files while writing
+    if !output_formats.contains(&OutputFormat::Sqlite) {
+        return Ok(());
     }
 
-    fs::create_dir_all(output_folder).unwrap();
+    let text_databases = languages.iter()
+        .map(|language| PathBuf::from(TEXT_DATABASES_ROOT).join(format!("{language}.sqlite")));
 
-    for database_path in DATABASES {
-        let path = output_folder.to_path_buf().join(database_path);
+    for database_path in DATABASES.iter().map(PathBuf::from).chain(text_databases) {
+        let path = output_folder.join(database_path);
 
         let parent = path.parent().unwrap();
-        fs::create_dir_all(parent).unwrap();
+        fs::create_dir_all(parent)?;
         
-        File::create(&path).unwrap();
+        // Existing databases are kept as is when mining incrementally
+        if !fs::exists(&path)? {
+            File::create(&path)?;
+        }
     }
 
-    for language in TEXT_LANGUAGES {
-        let path = output_folder.to_path_buf().join(TEXT_DATABASES_ROOT).join(format!("{language}.sqlite"));
+    Ok(())
+}
+
+/// Returns the folder the data is mined into before replacing the output folder.
+/// It is a sibling of the output folder, so that the final swap is a simple rename.
+pub fn staging_folder(output_folder: &Path) -> PathBuf {
+    let mut name = output_folder.file_name().unwrap_or(OsStr::new("output")).to_os_string();
+    name.push(".tmp");
 
-        let parent = path.parent().unwrap();
-        fs::create_dir_all(parent).unwrap();
-        
-        File::create(&path).unwrap();
+    output_folder.with_file_name(name)
+}
+
+/// Returns the folder the previous output is moved to while the staging folder replaces it.
+fn backup_folder(output_folder: &Path) -> PathBuf {
+    let staging = staging_folder(output_folder);
+
+    let mut name = staging.file_name().unwrap().to_os_string();
+    name.push(".old");
+
+    staging.with_file_name(name)
+}
+
+/// Creates an empty staging folder, or a copy of the current output folder
+/// when `incremental` is set, so that the existing databases get updated.
+pub fn prepare_staging_folder(output_folder: &Path, incremental: bool) -> io::Result<PathBuf> {
+    let staging = staging_folder(output_folder);
+    let backup = backup_folder(output_folder);
+
+    // A previous run might have crashed before cleaning up. Between the two renames
+    // of the swap, the only complete output is the backup, so it is put back
+    if fs::exists(&backup)? {
+        match fs::exists(output_folder)? {
+            true => fs::remove_dir_all(&backup)?,
+            false => fs::rename(&backup, output_folder)?,
+        }
+    }
+
+    if fs::exists(&staging)? {
+        fs::remove_dir_all(&staging)?;
+    }
+
+    if incremental && fs::exists(output_folder)? {
+        copy_dir_all(output_folder, &staging)?;
+    } else {
+        fs::create_dir_all(&staging)?;
+    }
+
+    Ok(staging)
+}
+
+/// Replaces the output folder with the staging folder. This is not atomic: the
+/// previous output is first renamed into a backup folder, then the staging folder
+/// takes its place, and the backup is only deleted once the new output is in place.
+/// If the program stops between the two renames, [`prepare_staging_folder`]
+/// restores the backup on the next run.
+pub fn commit_staging_folder(staging: &Path, output_folder: &Path) -> io::Result<()> {
+    let backup = backup_folder(output_folder);
+
+    if fs::exists(&backup)? {
+        fs::remove_dir_all(&backup)?;
+    }
+
+    let had_output = fs::exists(output_folder)?;
+
+    if had_output {
+        fs::rename(output_folder, &backup)?;
+    }
+
+    if let Err(e) = fs::rename(staging, output_folder) {
+        // We put the previous output back before failing
+        if had_output { fs::rename(&backup, output_folder)?; }
+        return Err(e);
+    }
+
+    if had_output {
+        fs::remove_dir_all(&backup)?;
+    }
+
+    Ok(())
+}
+
+pub fn discard_staging_folder(staging: &Path) {
+    if let Ok(true) = fs::exists(staging) {
+        let _ = fs::remove_dir_all(staging);
+    }
+}
+
+fn copy_dir_all(source: &Path, destination: &Path) -> io::Result<()> {
+    fs::create_dir_all(destination)?;
+
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let destination = destination.join(entry.file_name());
+
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &destination)?;
+        } else {
+            fs::copy(entry.path(), destination)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Extracts the game version from a versioned file name,
+/// `chara_param_1.4.0.0.cfg.bin` giving `[1, 4, 0, 0]`.
+pub fn parse_file_version(file_name: &str) -> Option<[u32; 4]> {
+    let stem = file_name.strip_suffix(".cfg.bin")?;
+    let version = stem.rsplit('_').next()?;
+
+    let mut parts = version.split('.').map(|p| p.parse::<u32>().ok());
+    let parsed = [parts.next()??, parts.next()??, parts.next()??, parts.next()??];
+
+    match parts.next() {
+        None => Some(parsed),
+        Some(_) => None,
+    }
+}
+
+/// The rules of a category of required files, whose regexes are compiled once.
+pub struct FileRules {
+    /// The identifier, pattern and regex of every rule, sorted by identifier
+    rules: Vec<(&'static str, &'static str, Regex)>,
+}
+
+impl FileRules {
+    pub fn new(rules: &HashMap<&'static str, &'static str>) -> FileRules {
+        let mut rules: Vec<_> = rules.iter()
+            .map(|(&identifier, &pattern)| (identifier, pattern, Regex::new(pattern).expect("the rules are valid regexes")))
+            .collect();
+
+        rules.sort_by_key(|(identifier, _, _)| *identifier);
+
+        FileRules { rules }
+    }
+
+    pub fn patterns(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.rules.iter().map(|(_, pattern, _)| *pattern)
+    }
+
+    /// Finds the file matching each rule in `folder`, relative to the extraction root.
+    /// When several versions of a file were extracted, the highest one is used.
+    pub fn resolve(&self, extraction_root: &Path, folder: &Path) -> io::Result<ResolvedFiles> {
+        let file_names: Vec<String> = match fs::read_dir(extraction_root.join(folder)) {
+            Ok(entries) => entries
+                .map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().into_owned()))
+                .collect::<io::Result<_>>()?,
+            // A missing folder simply means none of its files were extracted
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+
+        let folder = folder.to_string_lossy().replace('\\', "/").trim_end_matches('/').to_owned();
+        let mut resolved = ResolvedFiles::default();
+
+        for (identifier, pattern, regex) in &self.rules {
+            let mut candidates: Vec<&String> = file_names.iter()
+                .filter(|file_name| regex.is_match(file_name))
+                .collect();
+
+            // We sort by version, then by name, so the highest version is the last candidate
+            candidates.sort_by_key(|file_name| (parse_file_version(file_name), *file_name));
+
+            let Some(highest) = candidates.last().copied() else {
+                resolved.problems.push(FileProblem::Missing { folder: folder.clone(), identifier, rule: pattern });
+                continue;
+            };
+
+            let version = parse_file_version(highest);
+            let tied: Vec<String> = candidates.iter()
+                .filter(|file_name| parse_file_version(file_name) == version)
+                .map(|file_name| file_name.to_string())
+                .collect();
+
+            if tied.len() > 1 {
+                resolved.problems.push(FileProblem::Ambiguous { folder: folder.clone(), identifier, rule: pattern, candidates: tied });
+                continue;
+            }
+
+            resolved.files.insert(identifier, highest.clone());
+        }
+
+        Ok(resolved)
+    }
+}
+
+/// The files found for a category of rules, and the rules that could not be resolved.
+#[derive(Debug, Default)]
+pub struct ResolvedFiles {
+    files: HashMap<&'static str, String>,
+    pub problems: Vec<FileProblem>,
+}
+
+impl ResolvedFiles {
+    pub fn into_result(self) -> Result<ResolvedFiles, ResolveError> {
+        match self.problems.is_empty() {
+            true => Ok(self),
+            false => Err(ResolveError::Problems(self.problems)),
+        }
+    }
+
+    /// Takes the file of a rule, once the problems have been ruled out by `into_result`.
+    pub fn take(&mut self, identifier: &str) -> String {
+        self.files.remove(identifier).expect("every rule is resolved")
+    }
+}
+
+/// A required file that could not be resolved in the extraction folder.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FileProblem {
+    /// No file of the folder matches the rule
+    Missing { folder: String, identifier: &'static str, rule: &'static str },
+    /// Several files match the rule with the same version, so none can be picked
+    Ambiguous { folder: String, identifier: &'static str, rule: &'static str, candidates: Vec<String> },
+}
+
+impl FileProblem {
+    pub fn folder(&self) -> &str {
+        match self {
+            Self::Missing { folder, .. } | Self::Ambiguous { folder, .. } => folder,
+        }
+    }
+
+    pub fn identifier(&self) -> &'static str {
+        match self {
+            Self::Missing { identifier, .. } | Self::Ambiguous { identifier, .. } => identifier,
+        }
+    }
+
+    pub fn rule(&self) -> &'static str {
+        match self {
+            Self::Missing { rule, .. } | Self::Ambiguous { rule, .. } => rule,
+        }
+    }
+}
+
+impl std::fmt::Display for FileProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing { folder, rule, .. } => write!(f, "{folder}: no file matches {rule}"),
+            Self::Ambiguous { folder, rule, candidates, .. } => write!(f, "{folder}: {} files match {rule} with the same version ({})", candidates.len(), candidates.join(", ")),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ResolveError {
+    UnreadableFolder(io::Error),
+    Problems(Vec<FileProblem>),
+}
+
+impl From<io::Error> for ResolveError {
+    fn from(value: io::Error) -> Self {
+        ResolveError::UnreadableFolder(value)
     }
 }
 
-pub fn check_chara_files_existence(extraction_root_path: &Path) -> Option<HashMap<&'static str, String>> {
-    let chara_root = extraction_root_path.to_path_buf().join(CHARA_ROOT_PATH);
+impl Error for ResolveError {}
 
-    match fs::exists(&chara_root) {
-        Ok(bool) => if !bool { return Some(HashMap::new()) },
-        Err(_) => return None,
+impl std::fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnreadableFolder(e) => write!(f, "impossible to read the extraction folder ({e})"),
+            Self::Problems(problems) => write!(f, "unresolved game files: {}", problems.iter().map(|p| p.to_string()).collect::<Vec<_>>().join("; ")),
+        }
     }
+}
 
-    let mut files: HashMap<&str, String> = HashMap::new();
+/// Every required game file, resolved in the extraction folder.
+#[derive(Debug, Clone)]
+pub struct RequiredFiles {
+    pub characters: CharacterFiles,
+    /// The skill files, which are optional: without them, only the skills are not mined
+    pub skills: Option<SkillFiles>,
+    /// The text files of every language, by language code
+    pub text: BTreeMap<String, TextFiles>,
+    /// The problems of the optional files, which do not prevent the mining
+    pub skipped: Vec<FileProblem>,
+}
+
+impl RequiredFiles {
+    /// Resolves every required file, with the text files of each of the `languages`,
+    /// reporting all the problems at once.
+    pub fn resolve(extraction_root: &Path, languages: &[String]) -> Result<RequiredFiles, ResolveError> {
+        let mut problems = Vec::new();
+        let mut skipped = Vec::new();
 
-    for (identifier, rule) in CHARA_REQUIRED_FILES.iter() {
-        let regex = regex::Regex::new(rule).unwrap();
+        let characters = keep_problems(CharacterFiles::resolve(extraction_root), &mut problems)?;
+        let skills = keep_problems(SkillFiles::resolve(extraction_root), &mut skipped)?;
 
-        for file in fs::read_dir(&chara_root).ok()? {
-            let file = file.ok()?;
-            let file_name = file.file_name();
-            let file_name_str = file_name.to_string_lossy();
-            
-            if regex.is_match(&file_name_str) {
-                files.insert(identifier, file_name_str.to_string());
+        let mut text = BTreeMap::new();
+        for language in languages {
+            if let Some(files) = keep_problems(TextFiles::resolve(extraction_root, language), &mut problems)? {
+                text.insert(language.clone(), files);
             }
         }
+
+        match characters {
+            Some(characters) if problems.is_empty() => Ok(RequiredFiles { characters, skills, text, skipped }),
+            _ => Err(ResolveError::Problems(problems)),
+        }
     }
+}
+
+fn keep_problems<T>(result: Result<T, ResolveError>, problems: &mut Vec<FileProblem>) -> Result<Option<T>, ResolveError> {
+    match result {
+        Ok(files) => Ok(Some(files)),
+        Err(ResolveError::Problems(category_problems)) => {
+            problems.extend(category_problems);
+            Ok(None)
+        },
+        Err(e) => Err(e),
+    }
+}
 
-    Some(files)
+/// Lists the required files that cannot be resolved, which is empty when the data can be mined.
+pub fn check_required_files(extraction_root: &Path, languages: &[String]) -> io::Result<Vec<FileProblem>> {
+    match RequiredFiles::resolve(extraction_root, languages) {
+        Ok(_) => Ok(Vec::new()),
+        Err(ResolveError::Problems(problems)) => Ok(problems),
+        Err(ResolveError::UnreadableFolder(e)) => Err(e),
+    }
 }
 
-pub fn check_text_files_existence(extraction_root_path: &Path) -> Option<HashMap<&'static str, HashMap<&'static str, String>>> {
-    let text_root = extraction_root_path.to_path_buf().join(TEXT_ROOT_PATH);
+/// A required file missing from the extraction folder, the text files
+/// being required once per language.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Requirement {
+    /// The language of the text files
+    pub language: Option<String>,
+    pub folder: String,
+    pub identifier: &'static str,
+    pub rule: &'static str,
+}
 
-    match fs::exists(&text_root) {
-        Ok(bool) => if !bool { return Some(HashMap::new()) },
-        Err(_) => return None,
+impl std::fmt::Display for Requirement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{} ({})", self.folder, self.identifier, self.rule)
     }
+}
+
+/// The required files IEVR Toolbox has to extract.
+#[derive(Debug, Default)]
+pub struct ExtractionPlan {
+    pub requirements: Vec<Requirement>,
+    /// The languages whose text files are required
+    pub languages: Vec<String>,
+}
 
-    let mut files: HashMap<&str, HashMap<&str, String>> = HashMap::new();
+impl ExtractionPlan {
+    /// Lists every required file missing from the extraction folder, for each of the `languages`.
+    /// The ambiguous files are left out, extracting them again would not lift the ambiguity.
+    pub fn new(extraction_root: &Path, languages: &[String]) -> io::Result<ExtractionPlan> {
+        let mut plan = ExtractionPlan { languages: languages.to_vec(), ..Default::default() };
 
-    for (language, rules) in TEXT_REQUIRED_FILES.iter() {
-        files.insert(
-            *language, 
-            rules.clone()
-                .into_iter()
-                .map(|(k, v)| (k, v.to_owned()))
-                .collect()
-        );
+        plan.add_missing(None, CharacterFiles::resolve(extraction_root).err())?;
+        plan.add_missing(None, SkillFiles::resolve(extraction_root).err())?;
 
-        let language_path = text_root.join(language);
+        for language in languages {
+            plan.add_missing(Some(language.clone()), TextFiles::resolve(extraction_root, language).err())?;
+        }
 
-        let dir = fs::read_dir(&language_path).ok();
+        Ok(plan)
+    }
 
-        if dir.is_none() {
-            return Some(HashMap::new()); // We're missing a language, extract everything
+    fn add_missing(&mut self, language: Option<String>, error: Option<ResolveError>) -> io::Result<()> {
+        let problems = match error {
+            None => return Ok(()),
+            Some(ResolveError::UnreadableFolder(e)) => return Err(e),
+            Some(ResolveError::Problems(problems)) => problems,
         };
 
-        for file in dir? { // We now know it is some
-            for (identifier, rule) in rules.iter() {
-                let regex = regex::Regex::new(rule).unwrap();
-
-                let file = file.as_ref().ok()?;
-                let file_name = file.file_name();
-                let file_name_str = file_name.to_string_lossy();
-                
-                if regex.is_match(&file_name_str) {
-                    let value = files
-                        .get_mut(language)?
-                        .get_mut(identifier)?;
-
-                    *value = file_name_str.to_string();
-                }
+        for problem in problems {
+            if let FileProblem::Missing { folder, identifier, rule } = problem {
+                self.requirements.push(Requirement { language: language.clone(), folder, identifier, rule });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requirements.is_empty()
+    }
+
+    /// The rules to give to IEVR Toolbox. They only match file names, so the
+    /// rule of a text file is only written once for all the languages missing it.
+    pub fn rules(&self) -> Vec<&'static str> {
+        let mut rules = Vec::new();
+
+        for requirement in &self.requirements {
+            if !rules.contains(&requirement.rule) {
+                rules.push(requirement.rule);
             }
-        } 
+        }
+
+        rules
     }
 
-    Some(files)
-}
\ No newline at end of file
+    /// Returns the planned files that are still missing, once the extraction is done.
+    pub fn verify(&self, extraction_root: &Path) -> io::Result<Vec<Requirement>> {
+        let remaining = ExtractionPlan::new(extraction_root, &self.languages)?;
+
+        Ok(self.requirements.iter()
+            .filter(|requirement| remaining.requirements.contains(requirement))
+            .cloned()
+            .collect())
+    }
+}
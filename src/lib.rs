@@ -0,0 +1,198 @@
+//! Parsing of the *Inazuma Eleven Victory Road* game files into plain Rust values.
+//!
+//! [`GameData::load`] reads an extraction folder produced by IEVR Toolbox and
+//! returns the characters, skills and localized texts it contains. Every output
+//! format of the dataminer, see [`output::OutputSink`], is built from these values.
+
+use std::{collections::HashMap, error::Error, path::Path};
+
+pub mod characters;
+pub mod common;
+pub mod diff;
+pub mod dump;
+pub mod file_operations;
+pub mod metadata;
+pub mod output;
+pub mod portraits;
+pub mod schema;
+pub mod settings;
+pub mod skills;
+pub mod text;
+pub mod tools;
+pub mod validation;
+pub mod verify;
+
+pub use characters::{
+    Character,
+    CHARA_ROOT_PATH,
+    CHARA_REQUIRED_FILES,
+};
+
+pub use skills::{
+    Skill,
+    SKILL_ROOT_PATH,
+    SKILL_REQUIRED_FILES,
+};
+
+pub use text::{
+    LocalizedText,
+    TEXT_ROOT_PATH,
+    TEXT_REQUIRED_FILES,
+};
+
+use metadata::{SourceFile, read_sources};
+use validation::{MiningError, MiningErrorKind, ValidationReport};
+use text::LanguageError;
+
+use file_operations::{FileProblem, RequiredFiles, ResolveError};
+
+pub const DATABASES: [&str; 2] = [
+    "characters.sqlite",
+    "skills.sqlite",
+];
+
+pub const TEXT_DATABASES_ROOT: &str = "text";
+
+/// The database holding the texts of every language, when `merged_text` is set
+pub const MERGED_TEXT_DATABASE: &str = "text.sqlite";
+
+/// All the game data extracted by the dataminer.
+#[derive(Debug)]
+pub struct GameData {
+    /// Every valid character, heroes and basaras included, sorted by index
+    pub characters: Vec<Character>,
+    /// Maps the series ID stored in the game files to the series text ID
+    pub series: HashMap<i32, i32>,
+    pub skills: Vec<Skill>,
+    /// The texts of every language, by language code
+    pub text: HashMap<String, LocalizedText>,
+    /// The PNG portraits by character index, relative to the output folder.
+    /// Only filled once they are written, see [`portraits::write_portraits`]
+    pub portraits: HashMap<i32, String>,
+    /// The number of characters in the game files that were not valid
+    pub ignored_characters: u32,
+    /// The game files the data was extracted from
+    pub sources: Vec<SourceFile>,
+    /// The anomalies found in the game files, whose rows were skipped, and the warnings
+    pub report: ValidationReport,
+}
+
+/// How the game files are read by [`GameData::load_with`].
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    /// Fails on the first anomaly found in the game files, instead of skipping its row
+    pub strict: bool,
+    /// The languages to parse, every one of the extraction folder when empty
+    pub languages: Vec<String>,
+}
+
+impl GameData {
+    /// Parses every required game file under `extraction_root`, which
+    /// must already hold the files extracted by IEVR Toolbox.
+    pub fn load(extraction_root: &Path) -> Result<GameData, LoadError> {
+        Self::load_with(extraction_root, &LoadOptions::default())
+    }
+
+    pub fn load_with(extraction_root: &Path, options: &LoadOptions) -> Result<GameData, LoadError> {
+        let languages = text::select_languages(extraction_root, &options.languages)?;
+
+        let files = match RequiredFiles::resolve(extraction_root, &languages) {
+            Ok(files) => files,
+            Err(ResolveError::UnreadableFolder(e)) => return Err(LoadError::IOError(e)),
+            Err(ResolveError::Problems(problems)) => return Err(LoadError::MissingFiles(problems)),
+        };
+
+        let mut sources = read_sources(extraction_root, CHARA_ROOT_PATH, &files.characters.file_names(), None)?;
+        if let Some(skill_files) = &files.skills {
+            sources.extend(read_sources(extraction_root, SKILL_ROOT_PATH, &skill_files.file_names(), None)?);
+        }
+
+        for (language, text_files) in files.text.iter() {
+            let language_root = Path::new(TEXT_ROOT_PATH).join(language);
+            sources.extend(read_sources(extraction_root, &language_root.to_string_lossy(), &text_files.file_names(), Some(language))?);
+        }
+
+        // The text files are the heaviest ones, so they are parsed alongside the rest
+        let mut character_report = ValidationReport::new(options.strict);
+        let mut skill_report = ValidationReport::new(options.strict);
+        let mut text_report = ValidationReport::new(options.strict);
+
+        let ((character_data, skills), text) = rayon::join(
+            || (
+                characters::load_character_data(extraction_root, &files.characters, &mut character_report),
+                files.skills.as_ref().map(|skill_files| skills::load_skill_data(extraction_root, skill_files, &mut skill_report)),
+            ),
+            || text::load_text_data(extraction_root, &files.text, &mut text_report),
+        );
+
+        let (character_data, text) = (character_data?, text?);
+
+        // The skills are optional, so the rest of the data is mined without them, unless
+        // their file could not be read in strict mode
+        for problem in &files.skipped {
+            skill_report.warn(MiningError::new(problem.identifier(), MiningErrorKind::MissingOptionalFile { reason: problem.to_string() }));
+        }
+        let skills = match skills {
+            Some(skills) => skill_report.record(skills)?.unwrap_or_default(),
+            None => Vec::new(),
+        };
+
+        let mut report = ValidationReport::default();
+        report.extend(character_report);
+        report.extend(skill_report);
+        report.extend(text_report);
+
+        Ok(GameData {
+            characters: character_data.characters,
+            series: character_data.series,
+            skills,
+            text,
+            portraits: HashMap::new(),
+            ignored_characters: character_data.ignored_characters,
+            sources,
+            report,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    /// Some required game files are missing or ambiguous
+    MissingFiles(Vec<FileProblem>),
+    IOError(std::io::Error),
+    /// A game file could not be read, or holds an anomaly in strict mode
+    Invalid(MiningError),
+    /// The requested languages could not be selected
+    Languages(LanguageError),
+}
+
+impl From<LanguageError> for LoadError {
+    fn from(value: LanguageError) -> Self {
+        LoadError::Languages(value)
+    }
+}
+
+impl From<MiningError> for LoadError {
+    fn from(value: MiningError) -> Self {
+        LoadError::Invalid(value)
+    }
+}
+
+impl From<std::io::Error> for LoadError {
+    fn from(value: std::io::Error) -> Self {
+        LoadError::IOError(value)
+    }
+}
+
+impl Error for LoadError {}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingFiles(problems) => write!(f, "Unresolved game files: {}", problems.iter().map(|p| p.to_string()).collect::<Vec<_>>().join("; ")),
+            Self::IOError(e) => write!(f, "Filesystem error: {e}"),
+            Self::Invalid(e) => write!(f, "Invalid game file: {e}"),
+            Self::Languages(e) => write!(f, "Invalid languages: {e}"),
+        }
+    }
+}
@@ -1,47 +1,99 @@
-use crossbeam::channel::{self, Receiver, Sender};
-use rusqlite::Connection;
-
-mod characters;
-mod text;
-mod common;
-mod file_operations;
-mod settings;
-mod tools;
-
-use std::{collections::HashMap, fs, io::Write, path::{Path, PathBuf}, process::exit, sync::Arc, thread::{self, JoinHandle}};
-
-use file_operations::{
-    create_required_files,
-    check_chara_files_existence,
-    check_text_files_existence,
+mod cli;
+
+use std::{error::Error, fs, io::{IsTerminal, Write}, path::{Path, PathBuf}, process::exit};
+
+use ievr_dataminer::{
+    GameData,
+    LoadOptions,
+    diff::{DiffSource, GameDiff},
+    dump::{dump_game_files, find_game_files},
+    file_operations::{
+        create_required_files,
+        prepare_staging_folder,
+        commit_staging_folder,
+        discard_staging_folder,
+        check_required_files,
+        ExtractionPlan,
+    },
+    output::create_sink,
+    portraits::{PORTRAIT_RULE, find_face_archives, write_portraits},
+    text::{CoverageReport, select_languages},
+    settings::{Settings, SettingsOverrides},
+    tools::Tools,
+    verify::verify_databases,
 };
 
-use characters::{
-    populate_character_data,
-    CHARA_ROOT_PATH,
-    CHARA_REQUIRED_FILES,
-};
+use cli::{Cli, Command, USAGE};
 
-use text::{
-    populate_text_data,
-    TEXT_ROOT_PATH,
-    TEXT_REQUIRED_FILES,
-};
+/// The validation report written in the output folder
+const REPORT_FILE: &str = "report.json";
 
-use settings::Settings;
+/// The text coverage report written in the output folder
+const COVERAGE_FILE: &str = "coverage.json";
 
-use tools::Tools;
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
 
-const DATABASES: [&str; 2] = [
-    "characters.sqlite",
-    "skills.sqlite",
-];
+    // Without arguments and with a terminal attached, the program was most 
+    // likely started by double clicking on it, so we keep the window open
+    let interactive = args.is_empty() && std::io::stdin().is_terminal();
 
-const TEXT_DATABASES_ROOT: &str = "text";
+    let cli = match Cli::parse(args.into_iter()) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            exit(2)
+        }
+    };
 
-fn main() {
-    // If the settings file does not exist, we download it
-    if !fs::exists("settings.toml").unwrap() {
+    if cli.help {
+        println!("{USAGE}");
+        exit(0)
+    }
+
+    // Comparing versions does not rely on the settings
+    if cli.command == Command::Diff {
+        let result = diff(&cli.paths[0], &cli.paths[1], &cli.diff_output);
+
+        if let Err(e) = &result {
+            eprintln!("Aborting because of {e}");
+        }
+
+        exit(if result.is_ok() { 0 } else { 1 })
+    }
+
+    let settings = load_settings(&cli.settings_path, cli.overrides, interactive);
+
+    let result = match cli.command {
+        Command::Extract => extract(&settings),
+        Command::Mine => mine(&settings),
+        Command::Check => check(&settings),
+        Command::Export => extract(&settings).and_then(|_| mine(&settings)),
+        Command::Dump => dump(&cli.paths, &cli.dump_output, &settings),
+        Command::Verify => verify(&settings),
+        Command::Diff => unreachable!(),
+    };
+
+    let code = match result {
+        Ok(_) => 0,
+        Err(e) => { eprintln!("Aborting because of {e}") ; 1 }
+    };
+
+    if interactive {
+        pause();
+    }
+
+    exit(code)
+}
+
+fn load_settings(settings_path: &str, overrides: SettingsOverrides, interactive: bool) -> Settings {
+    let mut settings = if fs::exists(settings_path).unwrap() {
+        match Settings::parse(settings_path) {
+            Ok(s) => s,
+            Err(e) => { eprintln!("Aborting because of {e}") ; exit(1) }
+        }
+    } else if interactive {
+        // If the settings file does not exist, we download it
         println!("Settings file not found, downloading from GitHub...");
         match Settings::download() {
             Ok(_) => println!("Download completed, please update the contents of the settings file and restart the program."),
@@ -50,136 +102,216 @@ fn main() {
 
         pause();
         exit(1)
-    }
-
-    // We parse the settings
-    let settings = match Settings::parse("settings.toml") {
-        Ok(s) => s,
-        Err(e) => { eprintln!("Aborting because of {e}") ; exit(1) }
+    } else {
+        // When run from a script, the options can replace the settings file entirely
+        Settings::default_values()
     };
 
-    // We compute the paths
-    let output_folder_path = PathBuf::from(&settings.output_folder);
-    let extraction_root_path = Arc::new(PathBuf::from(&settings.extraction_folder));
+    settings.apply(overrides);
 
-    // We create the database files
-    create_required_files(&output_folder_path);
+    settings
+}
 
-    // We setup the required channels for communication between the threads
-    let (char_name_req_tx, char_name_req_rx) = channel::unbounded();
+/// Extracts the game files that are not already present in the extraction folder.
+fn extract(settings: &Settings) -> Result<(), Box<dyn Error>> {
+    let extraction_root_path = PathBuf::from(&settings.extraction_folder);
 
-    // We verify the presence of all required files
-    let mut rules_to_extract = get_missing_character_rules(&extraction_root_path);    
-    rules_to_extract.extend(get_missing_text_rules(&extraction_root_path));
+    // We list the required files that are missing, for every language
+    let languages = select_languages(&extraction_root_path, &settings.languages)?;
+    let plan = ExtractionPlan::new(&extraction_root_path, &languages)?;
 
     #[cfg(debug_assertions)]
-    println!("Rules not fullfilled: {:#?}", rules_to_extract);
+    println!("Files to extract: {:#?}", plan.requirements);
+
+    // The face textures are optional, so they are not part of the required files
+    let extract_portraits = settings.portraits && find_face_archives(&extraction_root_path)?.is_empty();
+
+    if plan.is_empty() && !extract_portraits {
+        println!("All the required game files are already extracted.");
+        return Ok(());
+    }
+
+    let mut rules = plan.rules();
+    if extract_portraits {
+        rules.push(PORTRAIT_RULE);
+    }
 
     // We initialize the required tools
     let tools = Tools::new();
 
-    // We extract missing files
-    if !rules_to_extract.is_empty() {
-        println!("Missing game files, starting game extraction...\n");
-        match tools.extract(&settings, rules_to_extract) {
-            Ok(_) => println!("\nGame extraction done.\n"),
-            Err(e) => {
-                eprintln!("Impossible to extract game files due to: {e}\nAborting...");
-                exit(1);
-            }
-        };
+    println!("{} missing game file(s), starting game extraction...\n", plan.requirements.len() + extract_portraits as usize);
+    match tools.extract(settings, rules) {
+        Ok(_) => println!("\nGame extraction done.\n"),
+        Err(e) => return Err(format!("impossible to extract game files due to: {e}").into()),
+    };
+
+    if extract_portraits && find_face_archives(&extraction_root_path)?.is_empty() {
+        println!("[EXTRACT]: IEVR Toolbox did not produce any face texture, the portraits will be missing.");
+    }
+
+    // IEVR Toolbox does not tell which rules it could not fulfill, so we check again
+    let remaining = plan.verify(&extraction_root_path)?;
+
+    if !remaining.is_empty() {
+        println!("[EXTRACT]: {} game file(s) were not produced by IEVR Toolbox:", remaining.len());
+        for requirement in &remaining {
+            println!("  {requirement}");
+        }
+
+        return Err(format!("{} required game file(s) could not be extracted", remaining.len()).into());
+    }
+
+    Ok(())
+}
+
+/// Parses the extracted game files and writes them in every output format.
+fn mine(settings: &Settings) -> Result<(), Box<dyn Error>> {
+    // We compute the paths
+    let output_folder_path = PathBuf::from(&settings.output_folder);
+    let extraction_root_path = PathBuf::from(&settings.extraction_folder);
+
+    let languages = select_languages(&extraction_root_path, &settings.languages)?;
+
+    if !check_required_files(&extraction_root_path, &languages)?.is_empty() {
+        return Err("missing or ambiguous game files, run the \"check\" command for details".into());
     }
 
     println!("Starting game data mining...");
 
-    // We start the different threads
-    let character_thread = create_character_thread(&output_folder_path, &extraction_root_path, char_name_req_tx);
-    let text_thread = create_text_thread(&output_folder_path, &extraction_root_path, char_name_req_rx);
+    let mut game_data = GameData::load_with(&extraction_root_path, &LoadOptions { strict: settings.strict, languages })?;
+
+    println!("[CHARACTERS]: {} character(s) ignored for being invalid.", game_data.ignored_characters);
+    game_data.report.print_summary();
 
-    // We wait for the program to finish
-    let _ = character_thread.join();
-    let _ = text_thread.join();
+    // Everything is written in a staging folder, which only replaces 
+    // the output folder once every format has been written
+    let staging_folder_path = prepare_staging_folder(&output_folder_path, settings.incremental)?;
+
+    let result = write_outputs(&mut game_data, &extraction_root_path, &staging_folder_path, settings)
+        .and_then(|_| Ok(commit_staging_folder(&staging_folder_path, &output_folder_path)?));
+
+    if result.is_err() {
+        discard_staging_folder(&staging_folder_path);
+        return result;
+    }
 
     println!("\nGame data mining done. Please open the \"{}\" folder to get the databases.", &settings.output_folder);
+
+    Ok(())
 }
 
-fn create_character_thread(output_folder_path: &Path, extraction_root_path: &Arc<PathBuf>, char_name_req_tx: Sender<(i32, i32)>) -> JoinHandle<()> {
-    let character_database = Connection::open(output_folder_path.join(DATABASES[0])).unwrap();
+fn write_outputs(game_data: &mut GameData, extraction_root_path: &Path, output_folder_path: &Path, settings: &Settings) -> Result<(), Box<dyn Error>> {
+    // We create the database files
+    let languages: Vec<String> = game_data.text.keys().cloned().collect();
+    create_required_files(output_folder_path, &settings.output_formats, &languages)?;
+
+    // The portraits are written first, so that the databases can point to them
+    if settings.portraits {
+        println!("[PORTRAITS]: the portraits are experimental, they may be missing or scrambled.");
+        let summary = write_portraits(extraction_root_path, output_folder_path, &game_data.characters)?;
+
+        println!("[PORTRAITS]: {} portrait(s) written, {} character(s) without a face texture.", summary.portraits.len(), summary.missing);
+        if !summary.failures.is_empty() {
+            println!("[PORTRAITS]: {} face texture(s) could not be converted:", summary.failures.len());
+            for (archive, e) in &summary.failures {
+                println!("  {}: {e}", archive.display());
+            }
+        }
 
-    let chara_requested_files = check_chara_files_existence(&extraction_root_path).unwrap();
+        game_data.portraits = summary.portraits;
+    }
 
-    let extraction_path_clone = extraction_root_path.clone();
-    thread::spawn(move || {
-        populate_character_data(&extraction_path_clone, character_database, chara_requested_files, char_name_req_tx);
-    })
-}
+    game_data.report.write(&output_folder_path.join(REPORT_FILE))?;
 
-fn create_text_thread(output_folder_path: &Path, extraction_root_path: &Arc<PathBuf>, char_name_req_rx: Receiver<(i32, i32)>) -> JoinHandle<()> {
-    let text_requested_files = check_text_files_existence(&extraction_root_path).unwrap();
+    let coverage = CoverageReport::new(game_data);
+    coverage.print_summary();
+    coverage.write(&output_folder_path.join(COVERAGE_FILE))?;
 
-    let mut text_databases = HashMap::with_capacity(text_requested_files.len());
-    for language in text_requested_files.keys() {
-        text_databases.insert(
-            *language, 
-            Connection::open(output_folder_path.join(TEXT_DATABASES_ROOT).join(format!("{language}.sqlite"))).unwrap()
-        );
+    for format in settings.output_formats.iter() {
+        let mut sink = create_sink(*format, output_folder_path, settings)?;
+        sink.write(game_data)?;
     }
 
-    let extraction_path_clone = extraction_root_path.clone();
-    thread::spawn(move || {
-        populate_text_data(&extraction_path_clone,text_databases, text_requested_files, char_name_req_rx);
-    })
+    Ok(())
 }
 
-fn get_missing_character_rules(extraction_root_path: &Arc<PathBuf>) -> Vec<&'static str> {
-    match check_chara_files_existence(extraction_root_path) {
-        Some(files) => {
-            let mut missing_rules = Vec::new();
-
-            if files.len() < CHARA_REQUIRED_FILES.len() { // We compute the rules not fullfilled
-                for (identifier, rule) in CHARA_REQUIRED_FILES.iter() {
-                    if !files.contains_key(identifier) {
-                        missing_rules.push(*rule);
-                    }
-                }
-            }
+/// Compares two versions of the game data, and writes the
+/// changelog and the JSON diff into `output_folder`.
+fn diff(old_path: &str, new_path: &str, output_folder: &str) -> Result<(), Box<dyn Error>> {
+    println!("Loading \"{old_path}\" and \"{new_path}\"...");
+
+    let old = DiffSource::load(Path::new(old_path))?;
+    let new = DiffSource::load(Path::new(new_path))?;
+
+    let game_diff = GameDiff::compute(&old, &new);
+
+    let output_folder_path = PathBuf::from(output_folder);
+    fs::create_dir_all(&output_folder_path)?;
+
+    fs::write(output_folder_path.join("changelog.md"), game_diff.to_markdown())?;
+    fs::write(output_folder_path.join("diff.json"), game_diff.to_json()?)?;
+
+    println!(
+        "{} added, {} removed and {} changed character(s). The changelog was written to \"{}\".", 
+        game_diff.added.len(), 
+        game_diff.removed.len(), 
+        game_diff.changed.len(),
+        output_folder_path.join("changelog.md").display()
+    );
+
+    Ok(())
+}
 
-            missing_rules
+/// Writes every table of the game files matching `patterns` into the `output` database.
+fn dump(patterns: &[String], output: &str, settings: &Settings) -> Result<(), Box<dyn Error>> {
+    let extraction_root_path = PathBuf::from(&settings.extraction_folder);
+
+    let mut files = Vec::new();
+
+    for pattern in patterns {
+        let matches = find_game_files(&extraction_root_path, pattern)?;
+
+        if matches.is_empty() {
+            return Err(format!("no game file matches \"{pattern}\"").into());
         }
-        None => {
-            eprintln!("Error while reading the extracted game files.");
-            exit(1);
+
+        for file in matches {
+            if !files.contains(&file) {
+                files.push(file);
+            }
         }
     }
+
+    let summary = dump_game_files(&files, Path::new(output))?;
+
+    println!(
+        "[DUMP]: {} table(s) and {} row(s) from {} file(s) were written to \"{output}\".",
+        summary.tables,
+        summary.rows,
+        summary.files
+    );
+
+    Ok(())
 }
 
-fn get_missing_text_rules(extraction_root_path: &Arc<PathBuf>) -> Vec<&'static str> {
-    match check_text_files_existence(&extraction_root_path) {
-        Some(files) => {
-            let mut missing_rules = Vec::new();
-
-            for (language, rules) in TEXT_REQUIRED_FILES.iter() {
-                if !files.contains_key(language) { // If there's a whole language missing
-                    for &value in rules.values() { missing_rules.push(value); }
-                    break;
-                }
-
-                if files[language].len() < rules.len() {
-                    for (&identifier, &rule) in rules.iter() {
-                        if !files[language].contains_key(identifier) {
-                            missing_rules.push(rule);
-                        }
-                    }
-                }
-            }
-            
-            missing_rules
-        }
-        None => {
-            eprintln!("Error while reading the extracted game files.");
-            exit(1);
-        }
+/// Reports the required game files missing from the extraction folder, or matched by several files.
+fn check(settings: &Settings) -> Result<(), Box<dyn Error>> {
+    let extraction_root_path = PathBuf::from(&settings.extraction_folder);
+
+    let languages = select_languages(&extraction_root_path, &settings.languages)?;
+    let problems = check_required_files(&extraction_root_path, &languages)?;
+
+    if problems.is_empty() {
+        println!("[CHECK]: all the required game files are present.");
+        return Ok(());
+    }
+
+    println!("[CHECK]: {} required game file(s) could not be resolved:", problems.len());
+    for problem in &problems {
+        println!("  {problem}");
     }
+
+    Err(format!("{} required game file(s) missing or ambiguous", problems.len()).into())
 }
 
 fn pause() {
@@ -192,4 +324,18 @@ fn pause() {
 
     let mut _unused = String::new();
     std::io::stdin().read_line(&mut _unused).expect("Failed to read line");
-}
\ No newline at end of file
+}
+
+/// Runs the integrity checks on the databases of the output folder, failing when one does not pass.
+fn verify(settings: &Settings) -> Result<(), Box<dyn Error>> {
+    let report = verify_databases(Path::new(&settings.output_folder))?;
+    report.print_summary();
+
+    if !report.is_success() {
+        return Err(format!("{} failed integrity check(s)", report.failed()).into());
+    }
+
+    println!("\nEvery integrity check passed.");
+
+    Ok(())
+}
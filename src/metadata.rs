@@ -0,0 +1,116 @@
+use std::{fs, io, path::Path, time::UNIX_EPOCH};
+
+use rusqlite::{Connection, params};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::file_operations::parse_file_version;
+
+pub const DATAMINER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// A game file the data was extracted from.
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceFile {
+    /// The path of the file, relative to the extraction folder
+    pub path: String,
+    /// The language of the file, for the text files
+    pub language: Option<String>,
+    /// The game version in the file name, when there is one
+    pub version: Option<[u32; 4]>,
+    /// The SHA-256 of the file's content, in hexadecimal
+    pub sha256: String,
+    /// When the file was extracted from the game, in seconds since the Unix epoch
+    pub extracted_at: Option<u64>,
+}
+
+impl SourceFile {
+    /// `root_path` is the folder of the file relative to the extraction root.
+    pub fn read(extraction_root: &Path, root_path: &Path, file_name: &str, language: Option<&str>) -> io::Result<SourceFile> {
+        let relative_path = root_path.join(file_name);
+        let full_path = extraction_root.join(&relative_path);
+
+        let content = fs::read(&full_path)?;
+        let sha256 = Sha256::digest(&content).iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+
+        let extracted_at = fs::metadata(&full_path)?
+            .modified().ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_secs());
+
+        Ok(SourceFile {
+            path: relative_path.to_string_lossy().replace('\\', "/"),
+            language: language.map(str::to_owned),
+            version: parse_file_version(file_name),
+            sha256,
+            extracted_at,
+        })
+    }
+
+    pub fn version_string(&self) -> Option<String> {
+        self.version.map(|v| v.map(|n| n.to_string()).join("."))
+    }
+}
+
+/// Reads every resolved file of a category, `file_names` being the
+/// names of the files resolved in `root_path`.
+pub fn read_sources(extraction_root: &Path, root_path: &str, file_names: &[&str], language: Option<&str>) -> io::Result<Vec<SourceFile>> {
+    let mut file_names = file_names.to_vec();
+    file_names.sort();
+
+    file_names.into_iter()
+        .map(|file_name| SourceFile::read(extraction_root, Path::new(root_path), file_name, language))
+        .collect()
+}
+
+/// Replaces the content of the `metadata` table of a database with the
+/// files it was built from.
+pub fn write_metadata<'a>(conn: &mut Connection, sources: impl Iterator<Item = &'a SourceFile>) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS metadata (
+            source_file         TEXT PRIMARY KEY,
+            version             TEXT,
+            version_major       INTEGER,
+            version_minor       INTEGER,
+            version_patch       INTEGER,
+            version_build       INTEGER,
+            sha256              TEXT NOT NULL,
+            extracted_at        TEXT,
+            dataminer_version   TEXT NOT NULL,
+            mined_at            TEXT NOT NULL
+        );",
+        ()
+    )?;
+
+    let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Exclusive)?;
+
+    {
+        tx.execute("DELETE FROM metadata;", ())?;
+
+        let mut stmt = tx.prepare_cached(
+            "INSERT INTO metadata (
+                source_file, version, version_major, version_minor, version_patch, version_build,
+                sha256, extracted_at, dataminer_version, mined_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, datetime(?8, 'unixepoch'), ?9, datetime('now'))"
+        )?;
+
+        for source in sources {
+            let version = source.version;
+
+            stmt.execute(params![
+                source.path,
+                source.version_string(),
+                version.map(|v| v[0]),
+                version.map(|v| v[1]),
+                version.map(|v| v[2]),
+                version.map(|v| v[3]),
+                source.sha256,
+                source.extracted_at.map(|t| t as i64),
+                DATAMINER_VERSION,
+            ])?;
+        }
+    }
+
+    tx.commit()
+}
@@ -0,0 +1,276 @@
+use std::{collections::{BTreeSet, HashMap}, error::Error, path::{Path, PathBuf}, str::FromStr};
+
+mod csv;
+mod json;
+#[cfg(feature = "parquet")]
+mod parquet;
+mod sqlite;
+
+pub use self::csv::CsvSink;
+pub use self::json::JsonSink;
+#[cfg(feature = "parquet")]
+pub use self::parquet::ParquetSink;
+pub use self::sqlite::SqliteSink;
+
+use crate::{GameData, Character, LocalizedText, Skill, characters::{MAX_LEVEL, Stats}, settings::Settings};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Sqlite,
+    Json,
+    Csv,
+    Parquet,
+}
+
+impl FromStr for OutputFormat {
+    type Err = OutputError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "sqlite" => Ok(OutputFormat::Sqlite),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "parquet" => Ok(OutputFormat::Parquet),
+            _ => Err(OutputError::UnknownFormat(s.to_owned())),
+        }
+    }
+}
+
+/// A destination for the extracted game data. Every output format
+/// implements it, and receives the same parsed data.
+pub trait OutputSink {
+    /// Writes the game data into the output folder, which already exists.
+    fn write(&mut self, game_data: &GameData) -> Result<(), Box<dyn Error>>;
+}
+
+pub fn create_sink(format: OutputFormat, output_folder: &Path, settings: &Settings) -> Result<Box<dyn OutputSink>, OutputError> {
+    let output_folder = output_folder.to_path_buf();
+
+    match format {
+        OutputFormat::Sqlite => Ok(Box::new(SqliteSink::new(output_folder, settings.merged_text))),
+        OutputFormat::Json => Ok(Box::new(JsonSink::new(output_folder, settings.json_inline_names))),
+        OutputFormat::Csv => Ok(Box::new(CsvSink::new(output_folder))),
+        #[cfg(feature = "parquet")]
+        OutputFormat::Parquet => Ok(Box::new(ParquetSink::new(output_folder))),
+        #[cfg(not(feature = "parquet"))]
+        OutputFormat::Parquet => Err(OutputError::UnsupportedFormat("parquet")),
+    }
+}
+
+/// A single column of a [`FlatTable`], the optional ones holding the NULL values of SQLite.
+pub(crate) enum Column {
+    Integer(Vec<i32>),
+    OptionalInteger(Vec<Option<i32>>),
+    Text(Vec<String>),
+    OptionalText(Vec<Option<String>>),
+}
+
+impl Column {
+    fn len(&self) -> usize {
+        match self {
+            Column::Integer(v) => v.len(),
+            Column::OptionalInteger(v) => v.len(),
+            Column::Text(v) => v.len(),
+            Column::OptionalText(v) => v.len(),
+        }
+    }
+}
+
+/// A table the way the tabular formats (CSV and Parquet) write it,
+/// mirroring the layout of the SQLite databases.
+pub(crate) struct FlatTable {
+    /// The path of the table relative to the output folder, without extension
+    pub path: PathBuf,
+    pub columns: Vec<(&'static str, Column)>,
+}
+
+impl FlatTable {
+    pub fn row_count(&self) -> usize {
+        self.columns.first().map(|(_, c)| c.len()).unwrap_or(0)
+    }
+}
+
+pub(crate) fn flat_tables(game_data: &GameData) -> Vec<FlatTable> {
+    let characters = &game_data.characters;
+    let int_column = |f: fn(&Character) -> i32| Column::Integer(characters.iter().map(f).collect());
+
+    let mut tables = vec![
+        FlatTable {
+            path: PathBuf::from("characters"),
+            columns: vec![
+                ("index_id",            int_column(|c| c.index)),
+                ("rarity",              int_column(|c| c.rarity)),
+                ("chara_base_id",       int_column(|c| c.chara_base_id)),
+                ("chara_param_id",      int_column(|c| c.chara_param_id)),
+                ("name_id",             int_column(|c| c.name_id)),
+                ("description_id",      int_column(|c| c.description_id)),
+                ("element",             int_column(|c| c.element as i32)),
+                ("main_position",       int_column(|c| c.main_position as i32)),
+                ("alt_position",        int_column(|c| c.alt_position as i32)),
+                ("style",               int_column(|c| c.style as i32)),
+                ("series_id",           int_column(|c| c.series_id)),
+                ("growth_pattern",      int_column(|c| c.growth_pattern as i32)),
+                ("chara_rank",          int_column(|c| c.chara_rank as i32)),
+                ("portrait_path",       Column::OptionalText(characters.iter().map(|c| game_data.portraits.get(&c.index).cloned()).collect())),
+            ],
+        },
+    ];
+
+    // The level 50 and 99 stats, one row per level
+    let stat_rows: Vec<(&Character, i32, &Stats)> = characters.iter()
+        .flat_map(|c| [(c, 50, &c.lvl50_stats), (c, MAX_LEVEL as i32, &c.lvl99_stats)])
+        .collect();
+    let stat_column = |f: fn(&(&Character, i32, &Stats)) -> i32| Column::Integer(stat_rows.iter().map(f).collect());
+
+    tables.push(FlatTable {
+        path: PathBuf::from("character_stats"),
+        columns: vec![
+            ("index_id",        stat_column(|(c, _, _)| c.index)),
+            ("rarity",          stat_column(|(c, _, _)| c.rarity)),
+            ("level",           stat_column(|(_, level, _)| *level)),
+            ("kick",            stat_column(|(_, _, s)| s.kick as i32)),
+            ("control",         stat_column(|(_, _, s)| s.control as i32)),
+            ("technique",       stat_column(|(_, _, s)| s.technique as i32)),
+            ("pressure",        stat_column(|(_, _, s)| s.pressure as i32)),
+            ("physical",        stat_column(|(_, _, s)| s.physical as i32)),
+            ("agility",         stat_column(|(_, _, s)| s.agility as i32)),
+            ("intelligence",    stat_column(|(_, _, s)| s.intelligence as i32)),
+        ],
+    });
+
+    // The technique paths, one row per learned skill
+    let (mut index_ids, mut rarities, mut slots, mut skill_ids) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for character in characters {
+        for (slot, skill_id) in character.skills.iter().enumerate() {
+            if *skill_id != 0 {
+                index_ids.push(character.index);
+                rarities.push(character.rarity);
+                slots.push(slot as i32);
+                skill_ids.push(*skill_id);
+            }
+        }
+    }
+
+    tables.push(FlatTable {
+        path: PathBuf::from("character_skills"),
+        columns: vec![
+            ("index_id",    Column::Integer(index_ids)),
+            ("rarity",      Column::Integer(rarities)),
+            ("slot",        Column::Integer(slots)),
+            ("skill_id",    Column::Integer(skill_ids)),
+        ],
+    });
+
+    let skills = &game_data.skills;
+    tables.push(FlatTable {
+        path: PathBuf::from("skills"),
+        columns: vec![
+            ("id",              Column::Integer(skills.iter().map(|s| s.id).collect())),
+            ("name_id",         Column::Integer(skills.iter().map(|s| s.name_id).collect())),
+            ("description_id",  Column::Integer(skills.iter().map(|s| s.description_id).collect())),
+            ("element",         Column::OptionalInteger(skills.iter().map(|s| s.element.id()).collect())),
+            ("kind",            Column::Integer(skills.iter().map(|s| s.kind).collect())),
+            ("power",           Column::Integer(skills.iter().map(|s| s.power as i32).collect())),
+            ("tp_cost",         Column::Integer(skills.iter().map(|s| s.tp_cost as i32).collect())),
+        ],
+    });
+
+    // The texts, with one folder per language. Like in SQLite, only the texts of the
+    // extracted characters and skills are written, and chara_add_info is kept whole
+    let name_ids: BTreeSet<i32> = characters.iter().map(|c| c.name_id).collect();
+    let description_ids: BTreeSet<i32> = characters.iter().map(|c| c.description_id).collect();
+
+    for (language, text) in game_data.text.iter() {
+        let text_tables = [
+            ("character_names",         "name",         &text.chara_names,          Some(&name_ids)),
+            ("character_names_roma",    "name",         &text.chara_roma_names,     Some(&name_ids)),
+            ("character_descriptions",  "description",  &text.chara_descriptions,   Some(&description_ids)),
+            ("series_names",            "name",         &text.series_names,         None),
+            ("add_info_texts",          "text",         &text.add_info_texts,       None),
+        ];
+
+        for (name, column, entries, ids) in text_tables {
+            let mut entries: Vec<(&i32, &String)> = entries.iter()
+                .filter(|(id, _)| ids.is_none_or(|ids| ids.contains(id)))
+                .collect();
+            entries.sort_by_key(|(id, _)| **id);
+
+            tables.push(FlatTable {
+                path: PathBuf::from(crate::TEXT_DATABASES_ROOT).join(language).join(name),
+                columns: vec![
+                    ("id",      Column::Integer(entries.iter().map(|(id, _)| **id).collect())),
+                    (column,    Column::Text(entries.iter().map(|(_, text)| (*text).clone()).collect())),
+                ],
+            });
+        }
+
+        let variant_tables = [
+            ("character_name_variants", &text.chara_name_variants,      Some(&name_ids)),
+            ("add_info_name_variants",  &text.add_info_name_variants,   None),
+        ];
+
+        for (name, entries, ids) in variant_tables {
+            tables.push(variant_table(language, name, entries, ids));
+        }
+
+        tables.push(skill_name_table(language, &game_data.skills, text));
+    }
+
+    tables
+}
+
+fn variant_table(language: &str, name: &str, entries: &HashMap<i32, HashMap<i32, String>>, ids: Option<&BTreeSet<i32>>) -> FlatTable {
+    let mut variants: Vec<(i32, i32, &String)> = entries.iter()
+        .filter(|(id, _)| ids.is_none_or(|ids| ids.contains(id)))
+        .flat_map(|(id, variants)| variants.iter().map(|(variant, text)| (*id, *variant, text)))
+        .collect();
+    variants.sort_by_key(|(id, variant, _)| (*id, *variant));
+
+    FlatTable {
+        path: PathBuf::from(crate::TEXT_DATABASES_ROOT).join(language).join(name),
+        columns: vec![
+            ("id",      Column::Integer(variants.iter().map(|(id, _, _)| *id).collect())),
+            ("variant", Column::Integer(variants.iter().map(|(_, variant, _)| *variant).collect())),
+            ("name",    Column::Text(variants.iter().map(|(_, _, text)| (*text).clone()).collect())),
+        ],
+    }
+}
+
+/// The names of the extracted skills with their description, which can be missing.
+fn skill_name_table(language: &str, skills: &[Skill], text: &LocalizedText) -> FlatTable {
+    let mut names: Vec<(i32, &String, Option<&String>)> = Vec::new();
+    let mut written = BTreeSet::new();
+
+    for skill in skills {
+        if let Some(name) = text.skill_names.get(&skill.name_id) && written.insert(skill.name_id) {
+            names.push((skill.name_id, name, text.skill_descriptions.get(&skill.description_id)));
+        }
+    }
+    names.sort_by_key(|(id, _, _)| *id);
+
+    FlatTable {
+        path: PathBuf::from(crate::TEXT_DATABASES_ROOT).join(language).join("skill_names"),
+        columns: vec![
+            ("id",          Column::Integer(names.iter().map(|(id, _, _)| *id).collect())),
+            ("name",        Column::Text(names.iter().map(|(_, name, _)| (*name).clone()).collect())),
+            ("description", Column::OptionalText(names.iter().map(|(_, _, description)| description.cloned()).collect())),
+        ],
+    }
+}
+
+#[derive(Debug)]
+pub enum OutputError {
+    UnknownFormat(String),
+    UnsupportedFormat(&'static str),
+}
+
+impl Error for OutputError {}
+
+impl std::fmt::Display for OutputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownFormat(format) => write!(f, "Unknown output format \"{format}\""),
+            Self::UnsupportedFormat(format) => write!(f, "This build does not support the \"{format}\" output format"),
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::{error::Error, fs, path::PathBuf};
+
+use crate::GameData;
+
+use super::{Column, OutputSink, flat_tables};
+
+/// Writes one CSV file per table, with the same layout as the SQLite databases.
+/// The NULL values are empty fields.
+pub struct CsvSink {
+    output_folder: PathBuf,
+}
+
+impl CsvSink {
+    pub fn new(output_folder: PathBuf) -> CsvSink {
+        CsvSink { output_folder }
+    }
+}
+
+impl OutputSink for CsvSink {
+    fn write(&mut self, game_data: &GameData) -> Result<(), Box<dyn Error>> {
+        for table in flat_tables(game_data) {
+            let path = self.output_folder.join(&table.path).with_extension("csv");
+            fs::create_dir_all(path.parent().unwrap())?;
+
+            let mut writer = ::csv::Writer::from_path(&path)?;
+
+            writer.write_record(table.columns.iter().map(|(name, _)| *name))?;
+
+            for row in 0..table.row_count() {
+                writer.write_record(table.columns.iter().map(|(_, column)| match column {
+                    Column::Integer(values) => values[row].to_string(),
+                    Column::OptionalInteger(values) => values[row].map(|v| v.to_string()).unwrap_or_default(),
+                    Column::Text(values) => values[row].clone(),
+                    Column::OptionalText(values) => values[row].clone().unwrap_or_default(),
+                }))?;
+            }
+
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use std::{error::Error, fs::{self, File}, io::BufWriter, path::{Path, PathBuf}};
+
+use serde::Serialize;
+
+use crate::{Character, GameData, LocalizedText, Skill, TEXT_DATABASES_ROOT, metadata::{DATAMINER_VERSION, SourceFile}};
+
+use super::OutputSink;
+
+/// Writes `characters.json`, `skills.json`, `metadata.json` and one `text/{language}.json` file
+/// per language. When the names are inlined, `characters.{language}.json` and
+/// `skills.{language}.json` are also written, holding the texts next to the IDs.
+pub struct JsonSink {
+    output_folder: PathBuf,
+    inline_names: bool,
+}
+
+#[derive(Serialize)]
+struct Metadata<'a> {
+    dataminer_version: &'static str,
+    sources: &'a [SourceFile],
+}
+
+#[derive(Serialize)]
+struct LocalizedCharacter<'a> {
+    #[serde(flatten)]
+    character: &'a Character,
+    name: Option<&'a String>,
+    roma_name: Option<&'a String>,
+    description: Option<&'a String>,
+    series_name: Option<&'a String>,
+}
+
+#[derive(Serialize)]
+struct LocalizedSkill<'a> {
+    #[serde(flatten)]
+    skill: &'a Skill,
+    name: Option<&'a String>,
+    description: Option<&'a String>,
+}
+
+impl JsonSink {
+    pub fn new(output_folder: PathBuf, inline_names: bool) -> JsonSink {
+        JsonSink { output_folder, inline_names }
+    }
+
+    fn write_file<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), Box<dyn Error>> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, value)?;
+        Ok(())
+    }
+
+    fn write_localized(&self, game_data: &GameData, language: &str, text: &LocalizedText) -> Result<(), Box<dyn Error>> {
+        let characters: Vec<LocalizedCharacter> = game_data.characters.iter()
+            .map(|character| LocalizedCharacter {
+                character,
+                name: text.chara_names.get(&character.name_id),
+                roma_name: text.chara_roma_names.get(&character.name_id),
+                description: text.chara_descriptions.get(&character.description_id),
+                series_name: text.series_names.get(&character.series_id),
+            })
+            .collect();
+
+        let skills: Vec<LocalizedSkill> = game_data.skills.iter()
+            .map(|skill| LocalizedSkill {
+                skill,
+                name: text.skill_names.get(&skill.name_id),
+                description: text.skill_descriptions.get(&skill.description_id),
+            })
+            .collect();
+
+        Self::write_file(&self.output_folder.join(format!("characters.{language}.json")), &characters)?;
+        Self::write_file(&self.output_folder.join(format!("skills.{language}.json")), &skills)
+    }
+}
+
+impl OutputSink for JsonSink {
+    fn write(&mut self, game_data: &GameData) -> Result<(), Box<dyn Error>> {
+        Self::write_file(&self.output_folder.join("characters.json"), &game_data.characters)?;
+        Self::write_file(&self.output_folder.join("skills.json"), &game_data.skills)?;
+        Self::write_file(&self.output_folder.join("metadata.json"), &Metadata { 
+            dataminer_version: DATAMINER_VERSION, 
+            sources: &game_data.sources 
+        })?;
+
+        let text_root = self.output_folder.join(TEXT_DATABASES_ROOT);
+        fs::create_dir_all(&text_root)?;
+
+        for (language, text) in game_data.text.iter() {
+            Self::write_file(&text_root.join(format!("{language}.json")), text)?;
+
+            if self.inline_names {
+                self.write_localized(game_data, language, text)?;
+            }
+        }
+
+        Ok(())
+    }
+}
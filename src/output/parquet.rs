@@ -0,0 +1,86 @@
+use std::{error::Error, fs::{self, File}, path::PathBuf, sync::Arc};
+
+use ::parquet::{
+    data_type::{ByteArray, ByteArrayType, Int32Type},
+    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    schema::parser::parse_message_type,
+};
+
+use crate::GameData;
+
+use super::{Column, FlatTable, OutputSink, flat_tables};
+
+/// Writes one Parquet file per table, with the same layout as the SQLite databases.
+pub struct ParquetSink {
+    output_folder: PathBuf,
+}
+
+impl ParquetSink {
+    pub fn new(output_folder: PathBuf) -> ParquetSink {
+        ParquetSink { output_folder }
+    }
+
+    fn write_table(&self, table: &FlatTable) -> Result<(), Box<dyn Error>> {
+        let path = self.output_folder.join(&table.path).with_extension("parquet");
+        fs::create_dir_all(path.parent().unwrap())?;
+
+        let fields: String = table.columns.iter()
+            .map(|(name, column)| match column {
+                Column::Integer(_) => format!("REQUIRED INT32 {name};"),
+                Column::OptionalInteger(_) => format!("OPTIONAL INT32 {name};"),
+                Column::Text(_) => format!("REQUIRED BYTE_ARRAY {name} (UTF8);"),
+                Column::OptionalText(_) => format!("OPTIONAL BYTE_ARRAY {name} (UTF8);"),
+            })
+            .collect();
+
+        let schema = Arc::new(parse_message_type(&format!("message table {{ {fields} }}"))?);
+        let properties = Arc::new(WriterProperties::builder().build());
+
+        let mut writer = SerializedFileWriter::new(File::create(&path)?, schema, properties)?;
+        let mut row_group = writer.next_row_group()?;
+
+        for (_, column) in table.columns.iter() {
+            let mut column_writer = row_group.next_column()?.ok_or("the Parquet schema has less columns than the table")?;
+
+            match column {
+                Column::Integer(values) => {
+                    column_writer.typed::<Int32Type>().write_batch(values, None, None)?;
+                }
+                Column::OptionalInteger(values) => {
+                    let present: Vec<i32> = values.iter().flatten().copied().collect();
+                    column_writer.typed::<Int32Type>().write_batch(&present, Some(&definition_levels(values)), None)?;
+                }
+                Column::Text(values) => {
+                    let values: Vec<ByteArray> = values.iter().map(|v| ByteArray::from(v.as_str())).collect();
+                    column_writer.typed::<ByteArrayType>().write_batch(&values, None, None)?;
+                }
+                Column::OptionalText(values) => {
+                    let present: Vec<ByteArray> = values.iter().flatten().map(|v| ByteArray::from(v.as_str())).collect();
+                    column_writer.typed::<ByteArrayType>().write_batch(&present, Some(&definition_levels(values)), None)?;
+                }
+            }
+
+            column_writer.close()?;
+        }
+
+        row_group.close()?;
+        writer.close()?;
+
+        Ok(())
+    }
+}
+
+/// The definition levels of an optional column, 0 marking the NULL values.
+fn definition_levels<T>(values: &[Option<T>]) -> Vec<i16> {
+    values.iter().map(|value| value.is_some() as i16).collect()
+}
+
+impl OutputSink for ParquetSink {
+    fn write(&mut self, game_data: &GameData) -> Result<(), Box<dyn Error>> {
+        for table in flat_tables(game_data) {
+            self.write_table(&table)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use std::{error::Error, path::PathBuf, thread};
+
+use crossbeam::channel;
+use rusqlite::Connection;
+
+use crate::{
+    CHARA_ROOT_PATH, DATABASES, GameData, MERGED_TEXT_DATABASE, SKILL_ROOT_PATH, TEXT_DATABASES_ROOT,
+    metadata::write_metadata,
+    characters::write_character_data,
+    skills::write_skill_data,
+    text::{write_merged_text_data, write_text_data},
+};
+
+use super::OutputSink;
+
+/// Writes `characters.sqlite`, `skills.sqlite` and one `text/{language}.sqlite`
+/// database per language, each database being written by its own thread. With
+/// `merged_text`, `text.sqlite` also holds the texts of every language.
+pub struct SqliteSink {
+    output_folder: PathBuf,
+    merged_text: bool,
+}
+
+impl SqliteSink {
+    pub fn new(output_folder: PathBuf, merged_text: bool) -> SqliteSink {
+        SqliteSink { output_folder, merged_text }
+    }
+}
+
+impl OutputSink for SqliteSink {
+    fn write(&mut self, game_data: &GameData) -> Result<(), Box<dyn Error>> {
+        let mut character_database = Connection::open(self.output_folder.join(DATABASES[0]))?;
+        let mut skill_database = Connection::open(self.output_folder.join(DATABASES[1]))?;
+
+        // Every database records the game files it was built from
+        write_metadata(&mut character_database, game_data.sources.iter().filter(|s| s.path.starts_with(CHARA_ROOT_PATH)))?;
+        write_metadata(&mut skill_database, game_data.sources.iter().filter(|s| s.path.starts_with(SKILL_ROOT_PATH)))?;
+
+        let mut text_databases = Vec::with_capacity(game_data.text.len());
+        for (language, text) in game_data.text.iter() {
+            let mut text_database = Connection::open(self.output_folder.join(TEXT_DATABASES_ROOT).join(format!("{language}.sqlite")))?;
+            write_metadata(&mut text_database, game_data.sources.iter().filter(|s| s.language.as_ref() == Some(language)))?;
+
+            text_databases.push((text_database, text));
+        }
+
+        // We setup the required channels for communication between the threads
+        let (char_name_req_tx, char_name_req_rx) = channel::unbounded();
+        let (skill_name_req_tx, skill_name_req_rx) = channel::unbounded();
+
+        // We start the different threads, and wait for them to finish
+        let results = thread::scope(|scope| {
+            let character_thread = scope.spawn(|| {
+                write_character_data(&mut character_database, &game_data.characters, &game_data.portraits, char_name_req_tx)
+            });
+
+            let skill_thread = scope.spawn(|| {
+                write_skill_data(&mut skill_database, &game_data.skills, skill_name_req_tx)
+            });
+
+            let text_thread = scope.spawn(|| {
+                write_text_data(text_databases, char_name_req_rx, skill_name_req_rx)
+            });
+
+            [character_thread.join(), skill_thread.join(), text_thread.join()]
+        });
+
+        for result in results {
+            match result {
+                Ok(result) => result.map_err(|e| format!("a database writer thread failed, the databases are incomplete: {e}"))?,
+                Err(_) => return Err("a database writer thread panicked, the databases are incomplete".into()),
+            }
+        }
+
+        if self.merged_text {
+            let mut merged_text_database = Connection::open(self.output_folder.join(MERGED_TEXT_DATABASE))?;
+            write_metadata(&mut merged_text_database, game_data.sources.iter().filter(|s| s.language.is_some()))?;
+
+            write_merged_text_data(&mut merged_text_database, &game_data.text, &game_data.characters, &game_data.skills)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+use std::{collections::{BTreeSet, HashMap}, error::Error, fs::{self, File}, io::{self, BufWriter}, path::{Path, PathBuf}};
+
+use rayon::prelude::*;
+
+use crate::Character;
+
+mod texture;
+
+pub use texture::{Texture, decode_g4tx};
+
+/// The folder of the face textures. Like [`PORTRAIT_RULE`], it has not been
+/// checked against the game files yet, which is why the portraits are experimental.
+pub const PORTRAIT_ROOT_PATH: &str = "data/common/ui/texture/face/";
+
+/// The rule giving the face texture archives to IEVR Toolbox. They are
+/// named after the index of their character, like `face_0042.g4tx`.
+pub const PORTRAIT_RULE: &str = "^face_\\d+\\.g4tx$";
+
+/// The folder of the output folder holding the PNG portraits
+pub const PORTRAITS_OUTPUT_ROOT: &str = "portraits";
+
+/// What was written by [`write_portraits`].
+#[derive(Debug, Default)]
+pub struct PortraitSummary {
+    /// The paths of the PNG portraits relative to the output folder, by character index
+    pub portraits: HashMap<i32, String>,
+    /// The number of characters without a face texture archive
+    pub missing: usize,
+    /// The face texture archives that could not be converted
+    pub failures: Vec<(PathBuf, PortraitError)>,
+}
+
+/// Lists the face texture archives of the extraction folder, by character index.
+pub fn find_face_archives(extraction_root: &Path) -> io::Result<HashMap<i32, PathBuf>> {
+    let folder = extraction_root.join(PORTRAIT_ROOT_PATH);
+
+    // The portraits are optional, so their folder may not have been extracted
+    if !fs::exists(&folder)? {
+        return Ok(HashMap::new());
+    }
+
+    let mut archives = HashMap::new();
+
+    for entry in fs::read_dir(folder)? {
+        let path = entry?.path();
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+
+        if let Some(index) = face_index(&file_name) {
+            archives.insert(index, path);
+        }
+    }
+
+    Ok(archives)
+}
+
+/// The character index in the name of a face texture archive matching [`PORTRAIT_RULE`].
+fn face_index(file_name: &str) -> Option<i32> {
+    let index = file_name.strip_prefix("face_")?.strip_suffix(".g4tx")?;
+
+    match index.bytes().all(|b| b.is_ascii_digit()) {
+        true => index.parse().ok(),
+        false => None,
+    }
+}
+
+/// Converts the face texture of every character into a PNG named after its
+/// index, in the `portraits` folder of `output_folder`. Every rarity of a
+/// character shares the same portrait.
+pub fn write_portraits(extraction_root: &Path, output_folder: &Path, characters: &[Character]) -> io::Result<PortraitSummary> {
+    let archives = find_face_archives(extraction_root)?;
+    fs::create_dir_all(output_folder.join(PORTRAITS_OUTPUT_ROOT))?;
+
+    let mut summary = PortraitSummary::default();
+    let mut conversions = Vec::new();
+
+    let indices: BTreeSet<i32> = characters.iter().map(|c| c.index).collect();
+    for index in indices {
+        match archives.get(&index) {
+            Some(archive) => conversions.push((index, archive)),
+            None => summary.missing += 1,
+        }
+    }
+
+    // Decoding the textures is the slow part, so the archives are converted in parallel
+    let results: Vec<_> = conversions.par_iter()
+        .map(|(index, archive)| {
+            let relative_path = format!("{PORTRAITS_OUTPUT_ROOT}/{index}.png");
+
+            let result = decode_g4tx(archive)
+                .and_then(|texture| write_png(&output_folder.join(&relative_path), &texture));
+
+            (*index, *archive, relative_path, result)
+        })
+        .collect();
+
+    for (index, archive, relative_path, result) in results {
+        match result {
+            Ok(()) => { summary.portraits.insert(index, relative_path); },
+            Err(e) => summary.failures.push((archive.clone(), e)),
+        }
+    }
+
+    Ok(summary)
+}
+
+fn write_png(path: &Path, texture: &Texture) -> Result<(), PortraitError> {
+    let file = File::create(path).map_err(PortraitError::UnwritableFile)?;
+
+    let mut encoder = png::Encoder::new(BufWriter::new(file), texture.width, texture.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header().map_err(PortraitError::Encoding)?;
+    writer.write_image_data(&texture.pixels).map_err(PortraitError::Encoding)?;
+
+    writer.finish().map_err(PortraitError::Encoding)
+}
+
+#[derive(Debug)]
+pub enum PortraitError {
+    UnreadableFile(io::Error),
+    UnwritableFile(io::Error),
+    /// The archive does not have the expected layout
+    InvalidArchive(&'static str),
+    /// The pixel format of the texture, by its ID, cannot be decoded yet
+    UnsupportedFormat(u32),
+    Encoding(png::EncodingError),
+}
+
+impl Error for PortraitError {}
+
+impl std::fmt::Display for PortraitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnreadableFile(e) => write!(f, "unreadable archive ({e})"),
+            Self::UnwritableFile(e) => write!(f, "unwritable PNG ({e})"),
+            Self::InvalidArchive(reason) => write!(f, "invalid archive ({reason})"),
+            Self::UnsupportedFormat(id) => write!(f, "unsupported pixel format 0x{id:02X}"),
+            Self::Encoding(e) => write!(f, "PNG encoding failed ({e})"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn face_index_reads_the_archive_name() {
+        assert_eq!(face_index("face_0042.g4tx"), Some(42));
+        assert_eq!(face_index("face_1.g4tx"), Some(1));
+    }
+
+    #[test]
+    fn face_index_ignores_other_files() {
+        assert_eq!(face_index("face_0042.png"), None);
+        assert_eq!(face_index("face_.g4tx"), None);
+        assert_eq!(face_index("face_+42.g4tx"), None);
+        assert_eq!(face_index("body_0042.g4tx"), None);
+    }
+}
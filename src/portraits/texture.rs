@@ -0,0 +1,338 @@
+use std::{fs, path::Path};
+
+use super::PortraitError;
+
+const G4TX_MAGIC: &[u8] = b"G4TX";
+const NXTCH_MAGIC: &[u8] = b"NXTCH000";
+
+// The offsets of the NXTCH header and the format IDs below have not been checked against
+// the face textures of the game yet: a texture that does not match them fails to decode,
+// or gives a scrambled PNG. The header field holding the block height of the swizzling
+// has not been identified either, so the block height the Switch GPU picks by default
+// for the height of the texture is used, see `default_block_height`.
+
+/// Where the pixels start in a NXTCH texture, after its header and mipmap offsets
+const NXTCH_DATA_OFFSET: usize = 0x100;
+
+/// A decoded texture, as 8-bit RGBA pixels.
+#[derive(Debug)]
+pub struct Texture {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+/// The pixel formats the textures can be decoded from.
+#[derive(Debug, Clone, Copy)]
+enum PixelFormat {
+    Rgba8,
+    Bc1,
+    Bc3,
+}
+
+impl PixelFormat {
+    /// The formats by their NXTCH format ID, the other ones are not supported yet
+    fn from_id(id: u32) -> Option<PixelFormat> {
+        match id {
+            0x25 => Some(PixelFormat::Rgba8),
+            0x42 => Some(PixelFormat::Bc1),
+            0x44 => Some(PixelFormat::Bc3),
+            _ => None,
+        }
+    }
+
+    /// The width and height of the blocks the pixels are stored in
+    fn block_dimension(self) -> usize {
+        match self {
+            PixelFormat::Rgba8 => 1,
+            PixelFormat::Bc1 | PixelFormat::Bc3 => 4,
+        }
+    }
+
+    /// The number of bytes of a block
+    fn block_size(self) -> usize {
+        match self {
+            PixelFormat::Rgba8 => 4,
+            PixelFormat::Bc1 => 8,
+            PixelFormat::Bc3 => 16,
+        }
+    }
+}
+
+/// Decodes the first texture of a G4TX archive. The face archives only hold one texture.
+pub fn decode_g4tx(path: &Path) -> Result<Texture, PortraitError> {
+    let data = fs::read(path).map_err(PortraitError::UnreadableFile)?;
+
+    if !data.starts_with(G4TX_MAGIC) {
+        return Err(PortraitError::InvalidArchive("not a G4TX archive"));
+    }
+
+    let header_size = read_u16(&data, 0x04)? as usize;
+    let table_size = read_u32(&data, 0x0C)? as usize;
+
+    if read_u16(&data, 0x20)? == 0 {
+        return Err(PortraitError::InvalidArchive("no texture in the archive"));
+    }
+
+    // The texture entries follow the header, their NXTCH offsets being relative to the end of the table
+    let nxtch_offset = read_u32(&data, header_size + 0x04)? as usize;
+    let nxtch_size = read_u32(&data, header_size + 0x08)? as usize;
+
+    let nxtch = (header_size + table_size).checked_add(nxtch_offset)
+        .and_then(|start| data.get(start..start.checked_add(nxtch_size)?))
+        .ok_or(PortraitError::InvalidArchive("texture outside of the archive"))?;
+
+    decode_nxtch(nxtch)
+}
+
+fn decode_nxtch(nxtch: &[u8]) -> Result<Texture, PortraitError> {
+    if !nxtch.starts_with(NXTCH_MAGIC) {
+        return Err(PortraitError::InvalidArchive("not a NXTCH texture"));
+    }
+
+    let width = read_u32(nxtch, 0x14)?;
+    let height = read_u32(nxtch, 0x18)?;
+    let format_id = read_u32(nxtch, 0x24)?;
+
+    let format = PixelFormat::from_id(format_id).ok_or(PortraitError::UnsupportedFormat(format_id))?;
+
+    if width == 0 || height == 0 {
+        return Err(PortraitError::InvalidArchive("empty texture"));
+    }
+
+    let (width, height) = (width as usize, height as usize);
+    let dimension = format.block_dimension();
+
+    let (width_in_blocks, height_in_blocks) = (width.div_ceil(dimension), height.div_ceil(dimension));
+
+    let data = nxtch.get(NXTCH_DATA_OFFSET..).unwrap_or_default();
+    let blocks = deswizzle(data, width_in_blocks, height_in_blocks, format.block_size(), default_block_height(height_in_blocks))?;
+
+    let pixels = match format {
+        PixelFormat::Rgba8 => blocks,
+        PixelFormat::Bc1 => decode_blocks(&blocks, width, height, format.block_size(), decode_bc1),
+        PixelFormat::Bc3 => decode_blocks(&blocks, width, height, format.block_size(), decode_bc3),
+    };
+
+    Ok(Texture { width: width as u32, height: height as u32, pixels })
+}
+
+/// The number of GOBs stacked vertically that the Switch GPU uses by default
+/// for a texture of `height` rows of blocks.
+fn default_block_height(height: usize) -> usize {
+    height.div_ceil(8).next_power_of_two().clamp(1, 16)
+}
+
+/// Reorders the blocks of a texture stored in the block linear layout of the
+/// Switch GPU, where the blocks are grouped in GOBs of 64 bytes by 8 rows,
+/// `block_height` GOBs being stacked vertically.
+fn deswizzle(data: &[u8], width: usize, height: usize, block_size: usize, block_height: usize) -> Result<Vec<u8>, PortraitError> {
+    let width_in_gobs = (width * block_size).div_ceil(64);
+
+    // The swizzled data is padded to whole GOB blocks. Its size is checked before allocating
+    // anything, so that a corrupted header cannot make us allocate gigabytes
+    let swizzled_size = height.div_ceil(8 * block_height)
+        .checked_mul(512 * block_height * width_in_gobs)
+        .ok_or(PortraitError::InvalidArchive("texture too large"))?;
+
+    if data.len() < swizzled_size {
+        return Err(PortraitError::InvalidArchive("truncated texture data"));
+    }
+
+    let mut linear = vec![0; width * height * block_size];
+
+    for y in 0..height {
+        for x in 0..width {
+            let offset = gob_offset(x * block_size, y, width_in_gobs, block_height);
+
+            let block = data.get(offset..offset + block_size)
+                .ok_or(PortraitError::InvalidArchive("truncated texture data"))?;
+
+            let destination = (y * width + x) * block_size;
+            linear[destination..destination + block_size].copy_from_slice(block);
+        }
+    }
+
+    Ok(linear)
+}
+
+/// The offset of the byte at column `x` (in bytes) and row `y` of a block linear texture.
+fn gob_offset(x: usize, y: usize, width_in_gobs: usize, block_height: usize) -> usize {
+    let gob_address = (y / (8 * block_height)) * 512 * block_height * width_in_gobs
+        + (x / 64) * 512 * block_height
+        + (y % (8 * block_height) / 8) * 512;
+
+    let (x, y) = (x % 64, y % 8);
+
+    gob_address + (x / 32) * 256 + (y / 2) * 64 + (x % 32 / 16) * 32 + (y % 2) * 16 + x % 16
+}
+
+/// Writes the 4x4 pixel blocks of a compressed texture into RGBA rows.
+fn decode_blocks(blocks: &[u8], width: usize, height: usize, block_size: usize, decode_block: fn(&[u8]) -> [[u8; 4]; 16]) -> Vec<u8> {
+    let mut pixels = vec![0; width * height * 4];
+    let width_in_blocks = width.div_ceil(4);
+
+    for (i, block) in blocks.chunks_exact(block_size).enumerate() {
+        let (block_x, block_y) = (i % width_in_blocks * 4, i / width_in_blocks * 4);
+
+        for (j, color) in decode_block(block).iter().enumerate() {
+            let (x, y) = (block_x + j % 4, block_y + j / 4);
+
+            // The blocks on the edges can go past the texture
+            if x < width && y < height {
+                let offset = (y * width + x) * 4;
+                pixels[offset..offset + 4].copy_from_slice(color);
+            }
+        }
+    }
+
+    pixels
+}
+
+fn decode_bc1(block: &[u8]) -> [[u8; 4]; 16] {
+    bc1_colors(block, true)
+}
+
+/// Decodes the colors of a BC1 block. Only BC1 itself can use its
+/// second mode, where the last color is transparent.
+fn bc1_colors(block: &[u8], transparency: bool) -> [[u8; 4]; 16] {
+    let color_0 = u16::from_le_bytes([block[0], block[1]]);
+    let color_1 = u16::from_le_bytes([block[2], block[3]]);
+    let (endpoint_0, endpoint_1) = (rgb565(color_0), rgb565(color_1));
+
+    let mix = |weight_0: u32, weight_1: u32| -> [u8; 4] {
+        std::array::from_fn(|channel| {
+            ((endpoint_0[channel] as u32 * weight_0 + endpoint_1[channel] as u32 * weight_1) / (weight_0 + weight_1)) as u8
+        })
+    };
+
+    let palette = if color_0 > color_1 || !transparency {
+        [endpoint_0, endpoint_1, mix(2, 1), mix(1, 2)]
+    } else {
+        [endpoint_0, endpoint_1, mix(1, 1), [0, 0, 0, 0]]
+    };
+
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+
+    std::array::from_fn(|i| palette[(indices >> (2 * i) & 0b11) as usize])
+}
+
+fn decode_bc3(block: &[u8]) -> [[u8; 4]; 16] {
+    let mut colors = bc1_colors(&block[8..16], false);
+
+    let (alpha_0, alpha_1) = (block[0] as u32, block[1] as u32);
+
+    // The alphas are interpolated between the two endpoints, with 0 and 255 in the second mode
+    let alphas: [u8; 8] = std::array::from_fn(|i| match i {
+        0 => alpha_0 as u8,
+        1 => alpha_1 as u8,
+        i if alpha_0 > alpha_1 => (((8 - i as u32) * alpha_0 + (i as u32 - 1) * alpha_1) / 7) as u8,
+        6 => 0,
+        7 => 255,
+        i => (((6 - i as u32) * alpha_0 + (i as u32 - 1) * alpha_1) / 5) as u8,
+    });
+
+    let indices = u64::from_le_bytes([block[2], block[3], block[4], block[5], block[6], block[7], 0, 0]);
+
+    for (i, color) in colors.iter_mut().enumerate() {
+        color[3] = alphas[(indices >> (3 * i) & 0b111) as usize];
+    }
+
+    colors
+}
+
+fn rgb565(color: u16) -> [u8; 4] {
+    let (r, g, b) = ((color >> 11 & 0x1F) as u8, (color >> 5 & 0x3F) as u8, (color & 0x1F) as u8);
+
+    [r << 3 | r >> 2, g << 2 | g >> 4, b << 3 | b >> 2, 255]
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, PortraitError> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or(PortraitError::InvalidArchive("truncated header"))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, PortraitError> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or(PortraitError::InvalidArchive("truncated header"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+    /// A BC1 block whose pixels use the palette indices 0, 1, 2, 3 on every row
+    fn bc1_block(color_0: u16, color_1: u16) -> [u8; 8] {
+        let [c0_low, c0_high] = color_0.to_le_bytes();
+        let [c1_low, c1_high] = color_1.to_le_bytes();
+
+        [c0_low, c0_high, c1_low, c1_high, 0xE4, 0xE4, 0xE4, 0xE4]
+    }
+
+    #[test]
+    fn bc1_interpolates_two_thirds() {
+        let colors = decode_bc1(&bc1_block(0xF800, 0x001F));
+
+        assert_eq!(colors[..4], [RED, BLUE, [170, 0, 85, 255], [85, 0, 170, 255]]);
+        assert_eq!(colors[4..8], colors[..4]);
+    }
+
+    #[test]
+    fn bc1_has_a_transparent_color_when_the_endpoints_are_ordered() {
+        let colors = decode_bc1(&bc1_block(0x001F, 0xF800));
+
+        assert_eq!(colors[..4], [BLUE, RED, [127, 0, 127, 255], [0, 0, 0, 0]]);
+    }
+
+    #[test]
+    fn bc3_interpolates_the_alphas() {
+        // The first pixels use the alpha indices 0, 1 and 2, the others 0
+        let alpha_indices: u64 = 1 << 3 | 2 << 6;
+        let mut block = [0; 16];
+        block[0] = 255;
+        block[2..8].copy_from_slice(&alpha_indices.to_le_bytes()[..6]);
+        block[8..16].copy_from_slice(&bc1_block(0x0000, 0xFFFF));
+
+        let colors = decode_bc3(&block);
+
+        // Unlike BC1, the endpoint order does not make the fourth color transparent
+        assert_eq!(colors[..4], [[0, 0, 0, 255], [255, 255, 255, 0], [85, 85, 85, 218], [170, 170, 170, 255]]);
+    }
+
+    #[test]
+    fn gob_offsets_follow_the_block_linear_layout() {
+        // Within a GOB, the 16 bytes sectors are grouped by two rows and two columns
+        assert_eq!(gob_offset(0, 0, 1, 1), 0);
+        assert_eq!(gob_offset(15, 0, 1, 1), 15);
+        assert_eq!(gob_offset(0, 1, 1, 1), 16);
+        assert_eq!(gob_offset(16, 0, 1, 1), 32);
+        assert_eq!(gob_offset(0, 2, 1, 1), 64);
+        assert_eq!(gob_offset(32, 0, 1, 1), 256);
+
+        // The GOBs of a block are stacked vertically, and the blocks are laid out by rows
+        assert_eq!(gob_offset(0, 8, 2, 2), 512);
+        assert_eq!(gob_offset(64, 0, 2, 2), 1024);
+        assert_eq!(gob_offset(0, 16, 2, 2), 2048);
+    }
+
+    #[test]
+    fn gob_offsets_cover_every_byte_once() {
+        let mut offsets: Vec<usize> = (0..16).flat_map(|y| (0..128).map(move |x| gob_offset(x, y, 2, 2))).collect();
+        offsets.sort();
+
+        assert_eq!(offsets, (0..2048).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn truncated_texture_data_is_rejected() {
+        // A 64x64 RGBA texture needs 16 KiB of swizzled data
+        let result = deswizzle(&[0; 1024], 64, 64, 4, default_block_height(64));
+
+        assert!(matches!(result, Err(PortraitError::InvalidArchive("truncated texture data"))));
+    }
+}
@@ -0,0 +1,207 @@
+use ievr_cfg_bin_editor_core::Value;
+use serde::Serialize;
+
+/// The type of the values of a column, matching the variants of [`Value`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueType {
+    Byte,
+    Short,
+    Int,
+    UInt,
+    Float,
+    String,
+}
+
+impl ValueType {
+    pub fn of(value: &Value) -> ValueType {
+        match value {
+            Value::Byte(_) => ValueType::Byte,
+            Value::Short(_) => ValueType::Short,
+            Value::Int(_) => ValueType::Int,
+            Value::UInt(_) => ValueType::UInt,
+            Value::Float(_) => ValueType::Float,
+            Value::String(_) => ValueType::String,
+        }
+    }
+}
+
+impl std::fmt::Display for ValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Byte => write!(f, "byte"),
+            Self::Short => write!(f, "short"),
+            Self::Int => write!(f, "int"),
+            Self::UInt => write!(f, "uint"),
+            Self::Float => write!(f, "float"),
+            Self::String => write!(f, "string"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ColumnSchema {
+    pub name: &'static str,
+    pub index: usize,
+    pub value_type: ValueType,
+}
+
+const fn column(name: &'static str, index: usize, value_type: ValueType) -> ColumnSchema {
+    ColumnSchema { name, index, value_type }
+}
+
+/// The layout of a table from a given game version onwards.
+#[derive(Debug)]
+pub struct SchemaVersion {
+    /// The first game version using this layout
+    pub since: [u32; 4],
+    pub columns: &'static [ColumnSchema],
+}
+
+/// The columns the dataminer reads in a game table. The layouts are sorted
+/// by game version, so that a new layout can be added after an update
+/// without breaking the older game files.
+#[derive(Debug)]
+pub struct TableSchema {
+    pub name: &'static str,
+    pub versions: &'static [SchemaVersion],
+}
+
+impl TableSchema {
+    /// The layout used by a game file of the given version. The files without
+    /// a version in their name always use the latest layout.
+    pub fn columns(&self, version: Option<[u32; 4]>) -> &'static [ColumnSchema] {
+        let layout = match version {
+            Some(version) => self.versions.iter().rev().find(|layout| layout.since <= version),
+            None => self.versions.last(),
+        };
+
+        layout.or(self.versions.first())
+            .map(|layout| layout.columns)
+            .unwrap_or_default()
+    }
+}
+
+use ValueType::*;
+
+pub const CHARA_BASE_INFO: TableSchema = TableSchema {
+    name: "CHARA_BASE_INFO",
+    versions: &[SchemaVersion {
+        since: [0, 0, 0, 0],
+        columns: &[
+            column("id",                0,  Int),
+            column("index",             2,  Int),
+            column("name_id",           3,  Int),
+            column("series_id",         15, Int),
+            column("description_id",    19, Int),
+        ],
+    }],
+};
+
+pub const CHARA_PARAM_INFO: TableSchema = TableSchema {
+    name: "CHARA_PARAM_INFO",
+    versions: &[SchemaVersion {
+        since: [0, 0, 0, 0],
+        columns: &[
+            column("id",                0,  Int),
+            column("chara_base_id",     1,  Int),
+            column("element",           2,  Int),
+            column("main_position",     3,  Int),
+            column("alt_position",      4,  Int),
+            column("style",             5,  Int),
+            column("growth_pattern",    7,  Int),
+            column("chara_rank",        9,  Int),
+            // The technique paths, empty slots holding 0
+            column("skill_1",           23, Int),
+            column("skill_2",           24, Int),
+            column("skill_3",           25, Int),
+            column("skill_4",           26, Int),
+            column("skill_5",           27, Int),
+            column("skill_6",           28, Int),
+            column("rarity",            41, Int),
+        ],
+    }],
+};
+
+pub const CHARA_SERIES_INFO: TableSchema = TableSchema {
+    name: "m_charaSeriesInfoList",
+    versions: &[SchemaVersion {
+        since: [0, 0, 0, 0],
+        columns: &[
+            column("series_id",         0,  UInt),
+            column("text_id",           2,  UInt),
+        ],
+    }],
+};
+
+pub const GROWTH_TABLE_MAIN: TableSchema = TableSchema {
+    name: "m_growthTableMainList",
+    versions: &[SchemaVersion {
+        since: [0, 0, 0, 0],
+        columns: &[
+            column("main_position",         0,  Byte),
+            column("growth_pattern",        1,  Byte),
+            column("chara_rank",            2,  Byte),
+            // The base stats, the level 50 ones get multiplied by 1.4
+            column("lvl50_kick",            3,  Int),
+            column("lvl50_control",         4,  Int),
+            column("lvl50_technique",       5,  Int),
+            column("lvl50_pressure",        6,  Int),
+            column("lvl50_physical",        7,  Int),
+            column("lvl50_agility",         8,  Int),
+            column("lvl50_intelligence",    9,  Int),
+            column("lvl99_kick",            10, Int),
+            column("lvl99_control",         11, Int),
+            column("lvl99_technique",       12, Int),
+            column("lvl99_pressure",        13, Int),
+            column("lvl99_physical",        14, Int),
+            column("lvl99_agility",         15, Int),
+            column("lvl99_intelligence",    16, Int),
+        ],
+    }],
+};
+
+/// This layout has not been checked against a dump of `skill_config` yet, so
+/// every column past the ID is provisional. Run `ievr_dataminer dump
+/// "**/skill_config_*.cfg.bin"` on the game files to confirm it.
+pub const SKILL_CONFIG_INFO: TableSchema = TableSchema {
+    name: "SKILL_CONFIG_INFO",
+    versions: &[SchemaVersion {
+        since: [0, 0, 0, 0],
+        columns: &[
+            column("id",                0,  Int),
+            column("name_id",           1,  Int),
+            column("description_id",    2,  Int),
+            column("element",           3,  Int),
+            column("kind",              4,  Int),
+            column("power",             5,  Int),
+            column("tp_cost",           6,  Int),
+        ],
+    }],
+};
+
+/// The names of the text files
+pub const NOUN_INFO: TableSchema = TableSchema {
+    name: "NOUN_INFO",
+    versions: &[SchemaVersion {
+        since: [0, 0, 0, 0],
+        columns: &[
+            column("id",                0,  Int),
+            // Different from 0 when the text is an alternative of the main one
+            column("variant",           1,  Int),
+            column("text",              5,  String),
+        ],
+    }],
+};
+
+/// The longer texts of the text files, like the descriptions
+pub const TEXT_INFO: TableSchema = TableSchema {
+    name: "TEXT_INFO",
+    versions: &[SchemaVersion {
+        since: [0, 0, 0, 0],
+        columns: &[
+            column("id",                0,  Int),
+            column("text",              2,  String),
+        ],
+    }],
+};
@@ -2,16 +2,47 @@ use std::{error::Error, fs::File, io::Read};
 
 use reqwest::blocking::Client;
 
+use crate::output::OutputFormat;
+
+/// The settings file shipped with the dataminer, also downloaded when none is found
+const DEFAULT_SETTINGS: &str = include_str!("../settings.default.toml");
+
 #[derive(Debug)]
 pub struct Settings {
     pub output_folder: String,
     pub extraction_folder: String,
+    pub output_formats: Vec<OutputFormat>,
+    pub json_inline_names: bool,
+    pub incremental: bool,
+    pub strict: bool,
+    /// The languages to parse and output, every available one when empty
+    pub languages: Vec<String>,
+    pub merged_text: bool,
+    pub portraits: bool,
  
     pub game_folder: Option<String>,   
     pub threads: Option<i64>,
     pub memory: Option<f64>
 }
 
+/// Every value given on the command line, taking precedence
+/// over the ones from the settings file.
+#[derive(Debug, Default)]
+pub struct SettingsOverrides {
+    pub output_folder: Option<String>,
+    pub extraction_folder: Option<String>,
+    pub game_folder: Option<String>,
+    pub threads: Option<Option<i64>>,
+    pub memory: Option<Option<f64>>,
+    pub output_formats: Option<Vec<OutputFormat>>,
+    pub json_inline_names: Option<bool>,
+    pub incremental: Option<bool>,
+    pub strict: Option<bool>,
+    pub languages: Option<Vec<String>>,
+    pub merged_text: Option<bool>,
+    pub portraits: Option<bool>,
+}
+
 impl Settings {
     pub fn parse(settings_path: &str) -> Result<Self, SettingsError> {
         let mut settings_file = File::open(settings_path).map_err(|e| SettingsError::IOError(e))?;
@@ -19,7 +50,11 @@ impl Settings {
         let mut settings = String::new();
         settings_file.read_to_string(&mut settings).map_err(|e| SettingsError::IOError(e))?;
 
-        settings = settings.replace(r"\", r"\\"); // This solves escaping issues on Windows
+        Self::from_toml(&settings)
+    }
+
+    fn from_toml(settings: &str) -> Result<Self, SettingsError> {
+        let settings = settings.replace(r"\", r"\\"); // This solves escaping issues on Windows
 
         let settings_table = match settings.parse::<toml::Table>() {
             Ok(table) => table,
@@ -36,6 +71,40 @@ impl Settings {
             _ => return Err(SettingsError::ExtractionFolderError)
         };
 
+        // These were added later on, so older settings files may not have them
+        let output_formats = match settings_table["datamining"].get("output_format") {
+            None => vec![OutputFormat::Sqlite],
+            Some(toml::Value::String(s)) => vec![s.parse().map_err(|_| SettingsError::OutputFormatError)?],
+            Some(toml::Value::Array(formats)) if !formats.is_empty() => formats.iter()
+                .map(|f| match f {
+                    toml::Value::String(s) => s.parse().map_err(|_| SettingsError::OutputFormatError),
+                    _ => Err(SettingsError::OutputFormatError),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => return Err(SettingsError::OutputFormatError)
+        };
+
+        let json_inline_names = parse_bool(&settings_table["datamining"], "json_inline_names");
+
+        let incremental = parse_bool(&settings_table["datamining"], "incremental");
+
+        let strict = parse_bool(&settings_table["datamining"], "strict");
+
+        let languages = match settings_table["datamining"].get("languages") {
+            None => Vec::new(),
+            Some(toml::Value::Array(languages)) => languages.iter()
+                .map(|language| match language {
+                    toml::Value::String(s) if !s.is_empty() => Ok(s.clone()),
+                    _ => Err(SettingsError::LanguagesError),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => return Err(SettingsError::LanguagesError)
+        };
+
+        let merged_text = parse_bool(&settings_table["datamining"], "merged_text");
+
+        let portraits = parse_bool(&settings_table["datamining"], "portraits");
+
         let game_folder = match &settings_table["extraction"]["game_folder"] {
             toml::Value::String(s) if !s.is_empty() => Some(s.clone()),
             _ => None,
@@ -63,12 +132,40 @@ impl Settings {
             output_folder,
             game_folder,
             extraction_folder,
+            output_formats,
+            json_inline_names,
+            incremental,
+            strict,
+            languages,
+            merged_text,
+            portraits,
 
             threads,
             memory
         })
     }
 
+    /// The values of `settings.default.toml`, used when running 
+    /// from the command line without a settings file.
+    pub fn default_values() -> Self {
+        Self::from_toml(DEFAULT_SETTINGS).expect("the default settings file is valid")
+    }
+
+    pub fn apply(&mut self, overrides: SettingsOverrides) {
+        if let Some(output_folder) = overrides.output_folder { self.output_folder = output_folder }
+        if let Some(extraction_folder) = overrides.extraction_folder { self.extraction_folder = extraction_folder }
+        if let Some(game_folder) = overrides.game_folder { self.game_folder = Some(game_folder) }
+        if let Some(threads) = overrides.threads { self.threads = threads }
+        if let Some(memory) = overrides.memory { self.memory = memory }
+        if let Some(output_formats) = overrides.output_formats { self.output_formats = output_formats }
+        if let Some(json_inline_names) = overrides.json_inline_names { self.json_inline_names = json_inline_names }
+        if let Some(incremental) = overrides.incremental { self.incremental = incremental }
+        if let Some(strict) = overrides.strict { self.strict = strict }
+        if let Some(languages) = overrides.languages { self.languages = languages }
+        if let Some(merged_text) = overrides.merged_text { self.merged_text = merged_text }
+        if let Some(portraits) = overrides.portraits { self.portraits = portraits }
+    }
+
     pub fn download() -> Result<(), Box<dyn std::error::Error>> {
         // 1. Define the URL and the destination path
         let url = "https://raw.githubusercontent.com/Telmo26/ievr_dataminer/refs/heads/main/settings.default.toml";
@@ -95,12 +192,26 @@ impl Settings {
     }
 }
 
+/// Reads an optional boolean parameter, which is false when missing or incorrect.
+fn parse_bool(table: &toml::Value, key: &str) -> bool {
+    match table.get(key) {
+        Some(toml::Value::Boolean(b)) => *b,
+        None => false,
+        _ => {
+            eprintln!("Incorrect value for the {key} parameter, treating it as false");
+            false
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum SettingsError {
     IOError(std::io::Error),
     TomlParseError,
     OutputFolderError,
     ExtractionFolderError,
+    OutputFormatError,
+    LanguagesError,
     DownloadError(u16),
 }
 
@@ -113,6 +224,8 @@ impl std::fmt::Display for SettingsError {
             Self::TomlParseError => write!(f, "Invalid TOML format"),
             Self::OutputFolderError => write!(f, "Incorrect output folder."),
             Self::ExtractionFolderError => write!(f, "Incorrect extraction folder."),
+            Self::OutputFormatError => write!(f, "Incorrect output format, expected \"sqlite\", \"json\", \"csv\" or \"parquet\"."),
+            Self::LanguagesError => write!(f, "Incorrect languages, expected a list of language codes like [\"en\", \"ja\"]."),
             Self::DownloadError(code) => write!(f, "Download error, response code: {code}")
         }
     }
@@ -0,0 +1,180 @@
+use std::{collections::HashMap, path::Path, sync::LazyLock};
+
+use crossbeam::channel::Sender;
+
+mod skill;
+
+pub use skill::Skill;
+use rusqlite::{Connection, Result, params};
+
+use crate::{
+    characters::Element, 
+    common::{GameFile, GameTable}, 
+    file_operations::{FileRules, ResolveError},
+    schema,
+    validation::{MiningError, MiningErrorKind, ValidationReport},
+};
+
+pub const SKILL_ROOT_PATH: &str = "data/common/gamedata/skill/";
+
+pub static SKILL_REQUIRED_FILES: LazyLock<HashMap<&'static str, &'static str>> = LazyLock::new(|| {
+    let mut map = HashMap::new();
+    map.insert("skill_config", "^skill_config_\\d+\\.\\d+\\.\\d+\\.\\d+\\.cfg\\.bin$");
+    map
+});
+
+static SKILL_RULES: LazyLock<FileRules> = LazyLock::new(|| FileRules::new(&SKILL_REQUIRED_FILES));
+
+/// The names of the skill files resolved in the extraction folder.
+#[derive(Debug, Clone)]
+pub struct SkillFiles {
+    pub skill_config: String,
+}
+
+impl SkillFiles {
+    pub fn resolve(extraction_root: &Path) -> Result<SkillFiles, ResolveError> {
+        let mut files = SKILL_RULES.resolve(extraction_root, Path::new(SKILL_ROOT_PATH))?.into_result()?;
+
+        Ok(SkillFiles {
+            skill_config: files.take("skill_config"),
+        })
+    }
+
+    pub fn file_names(&self) -> [&str; 1] {
+        [&self.skill_config]
+    }
+}
+
+pub fn load_skill_data(extraction_path: &Path, files: &SkillFiles, report: &mut ValidationReport) -> Result<Vec<Skill>, MiningError> {
+    // We parse the game files
+    let root_path = extraction_path.to_path_buf().join(SKILL_ROOT_PATH);
+
+    let skill_config = GameFile::open(&root_path.join(&files.skill_config))?;
+    let skill_config_info = skill_config.table(&schema::SKILL_CONFIG_INFO, report)?;
+
+    let skills = get_skills(skill_config_info, report)?;
+
+    #[cfg(debug_assertions)]
+    println!("Nb of skills: {}", skills.len());
+
+    Ok(skills)
+}
+
+/// Writes the parsed skills into the database, and requests their
+/// names and descriptions from the text thread.
+pub fn write_skill_data(skill_database_connection: &mut Connection, skills: &[Skill], skill_name_req_tx: Sender<(i32, i32)>) -> Result<()> {
+    // Database operations
+    initialize_database(skill_database_connection)?;
+
+    skill_database_connection.pragma_update(None, "journal_mode", "WAL")?;
+    skill_database_connection.pragma_update(None, "synchronous", "NORMAL")?;
+
+    for skill in skills {
+        // The text thread only hangs up after failing, which it reports itself
+        let _ = skill_name_req_tx.send((skill.name_id, skill.description_id));
+    }
+
+    insert_skills(skill_database_connection, skills)
+}
+
+fn get_skills(skill_config_info: GameTable, report: &mut ValidationReport) -> Result<Vec<Skill>, MiningError> {
+    let mut skills: Vec<Skill> = Vec::new();
+
+    // The position of every skill in `skills`, by ID
+    let mut positions = HashMap::new();
+
+    for row in skill_config_info.rows() {
+        let skill = (|| Ok(Skill {
+            id:             row.int("id")?,
+            name_id:        row.int("name_id")?,
+            description_id: row.int("description_id")?,
+            element:        Element::from(row.int("element")?),
+            kind:           row.int("kind")?,
+            power:          row.u16("power")?,
+            tp_cost:        row.u16("tp_cost")?,
+        }))();
+
+        if let Some(skill) = report.record(skill)? 
+            && skill.id > 0 // The first rows of the table are empty placeholders
+        {
+            // Like for the texts, the last row defining a skill is kept
+            match positions.get(&skill.id) {
+                Some(&position) => {
+                    report.warn(row.error(Some("id"), MiningErrorKind::DuplicateSkill { id: skill.id }));
+                    skills[position] = skill;
+                },
+                None => {
+                    positions.insert(skill.id, skills.len());
+                    skills.push(skill);
+                },
+            }
+        }
+    }
+
+    skills.sort_by_key(|skill| skill.id);
+
+    Ok(skills)
+}
+
+fn initialize_database(database: &Connection) -> Result<()> {
+    // Older versions stored the unknown elements as the made-up value 5, in a column
+    // which could not be NULL. The skills are mined again anyway, so the table is recreated.
+    let legacy_skills: bool = database.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('skills') WHERE name = 'element' AND \"notnull\" = 1", 
+        [], 
+        |row| row.get(0)
+    )?;
+
+    if legacy_skills {
+        database.execute("DROP TABLE skills;", ())?;
+    }
+
+    database.execute(
+        "CREATE TABLE IF NOT EXISTS skills (
+            id              INTEGER PRIMARY KEY,
+            name_id         INTEGER NOT NULL,
+            description_id  INTEGER NOT NULL,
+            element         INTEGER,
+            kind            INTEGER NOT NULL,
+            power           INTEGER NOT NULL,
+            tp_cost         INTEGER NOT NULL
+        );",
+    ()
+    )?;
+
+    // Same as for the characters, the previous content is replaced
+    database.execute("DELETE FROM skills;", ())?;
+
+    Ok(())
+}
+
+fn insert_skills(conn: &mut Connection, skills: &[Skill]) -> rusqlite::Result<()> {
+    let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Exclusive)?;
+
+    {
+        let mut stmt = tx.prepare_cached(
+            "INSERT INTO skills (
+                id, name_id, description_id, element, kind, power, tp_cost
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            ON CONFLICT(id) DO UPDATE SET
+                name_id = excluded.name_id, description_id = excluded.description_id, element = excluded.element,
+                kind = excluded.kind, power = excluded.power, tp_cost = excluded.tp_cost"
+        )?;
+
+        for s in skills {
+            stmt.execute(
+                params![
+                    s.id,
+                    s.name_id,
+                    s.description_id,
+                    s.element.id(),
+                    s.kind,
+                    s.power,
+                    s.tp_cost,
+                ],
+            )?;
+        }
+    }
+
+    tx.commit()
+}
@@ -0,0 +1,15 @@
+use serde::Serialize;
+
+use crate::characters::Element;
+
+#[derive(Debug, Serialize)]
+pub struct Skill {
+    pub id: i32,
+    pub name_id: i32,
+    pub description_id: i32,
+    pub element: Element,
+    /// The kind of the skill as stored by the game, what each value means is not known yet
+    pub kind: i32,
+    pub power: u16,
+    pub tp_cost: u16,
+}
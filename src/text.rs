@@ -1,57 +1,171 @@
-use std::{collections::HashMap, path::Path, sync::LazyLock};
+use std::{collections::{BTreeMap, HashMap}, error::Error, fs, io, path::Path, sync::LazyLock};
 
 use crossbeam::channel::Receiver;
 use rayon::prelude::*;
 use rusqlite::Connection;
 
+mod coverage;
+mod localized_text;
+mod merged_database;
 mod text_database;
 
+pub use coverage::{CoverageReport, LanguageCoverage, TextCoverage};
+pub use localized_text::LocalizedText;
+pub use merged_database::write_merged_text_data;
 use text_database::TextDatabase;
 
-use crate::{
-    common::parse_gamefile,
-};
+use crate::{file_operations::{FileRules, ResolveError}, validation::{MiningError, ValidationReport}};
 
+/// The languages of the game, used until the text files are extracted
+/// and the languages of the extraction folder can be detected.
 pub const TEXT_LANGUAGES: [&str; 9] = [
     "de", "en", "es", "fr", "it", "ja", "pt", "zh_hans", "zh_hant"
 ];
 
 pub const TEXT_ROOT_PATH: &str = "data/common/text";
 
-pub const TEXT_REQUIRED_FILES: LazyLock<HashMap<&'static str, HashMap<&'static str, &'static str>>> = LazyLock::new(|| {
-    let mut map = HashMap::with_capacity(TEXT_LANGUAGES.len());
+/// The text files required in the folder of every language
+pub static TEXT_REQUIRED_FILES: LazyLock<HashMap<&'static str, &'static str>> = LazyLock::new(|| {
+    let mut map = HashMap::new();
+    map.insert("chara_add_info",       "^chara_add_info_text.cfg.bin$");
+    map.insert("chara_description",    "^chara_description_text.cfg.bin$");
+    map.insert("chara_text",           "^chara_text.cfg.bin$");
+    map.insert("chara_text_roma",      "^chara_text_roma.cfg.bin$");
+    map.insert("skill_text",           "^skill_text.cfg.bin$");
+    map
+});
+
+static TEXT_RULES: LazyLock<FileRules> = LazyLock::new(|| FileRules::new(&TEXT_REQUIRED_FILES));
 
-    for language in TEXT_LANGUAGES {
-        map.insert(language, HashMap::new());
-        let language_map = map.get_mut(language).unwrap();
+/// Lists the languages with a folder under the text folder of the extraction folder, sorted.
+pub fn available_languages(extraction_root: &Path) -> io::Result<Vec<String>> {
+    let mut languages = Vec::new();
 
-        language_map.insert("chara_add_info",       "^chara_add_info_text.cfg.bin$");
-        language_map.insert("chara_description",    "^chara_description_text.cfg.bin$");
-        language_map.insert("chara_text",           "^chara_text.cfg.bin$");
-        language_map.insert("chara_text_roma",      "^chara_text_roma.cfg.bin$");
-        language_map.insert("skill_text",           "^skill_text.cfg.bin$");
+    let entries = match fs::read_dir(extraction_root.join(TEXT_ROOT_PATH)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(languages),
+        Err(e) => return Err(e),
     };
-    map
-});
 
-pub fn populate_text_data(extraction_path: &Path, text_database_connections: HashMap<&'static str, Connection>, requested_files: HashMap<&'static str, HashMap<&'static str, String>>, char_name_req_rx: Receiver<(i32, i32)>) {
-    assert!(text_database_connections.len() == TEXT_LANGUAGES.len());
+    for entry in entries {
+        let entry = entry?;
+
+        if entry.file_type()?.is_dir() {
+            languages.push(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+
+    languages.sort();
+
+    Ok(languages)
+}
+
+/// The languages to work with: the `requested` ones, or every available one when
+/// none is requested. Before any text file is extracted, every known language is used.
+pub fn select_languages(extraction_root: &Path, requested: &[String]) -> Result<Vec<String>, LanguageError> {
+    let available = available_languages(extraction_root)?;
+
+    if requested.is_empty() {
+        return match available.is_empty() {
+            true => Ok(TEXT_LANGUAGES.iter().map(|language| language.to_string()).collect()),
+            false => Ok(available),
+        };
+    }
+
+    // The languages of the extraction folder are accepted too, in case a game update adds one
+    for language in requested {
+        if !TEXT_LANGUAGES.contains(&language.as_str()) && !available.contains(language) {
+            let mut known: Vec<String> = TEXT_LANGUAGES.iter().map(|language| language.to_string()).chain(available).collect();
+            known.sort();
+            known.dedup();
+
+            return Err(LanguageError::Unknown { language: language.clone(), known });
+        }
+    }
+
+    Ok(requested.to_vec())
+}
+
+#[derive(Debug)]
+pub enum LanguageError {
+    /// A requested language is neither a language of the game nor one of the extraction folder
+    Unknown { language: String, known: Vec<String> },
+    IOError(io::Error),
+}
 
-    #[cfg(debug_assertions)]
-    println!("{:#?}", requested_files);
+impl From<io::Error> for LanguageError {
+    fn from(value: io::Error) -> Self {
+        LanguageError::IOError(value)
+    }
+}
+
+impl Error for LanguageError {}
+
+impl std::fmt::Display for LanguageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unknown { language, known } => write!(f, "unknown language \"{language}\", expected one of {}", known.join(", ")),
+            Self::IOError(e) => write!(f, "impossible to list the languages of the extraction folder ({e})"),
+        }
+    }
+}
+
+/// The names of the text files of a language resolved in the extraction folder.
+#[derive(Debug, Clone)]
+pub struct TextFiles {
+    pub chara_add_info: String,
+    pub chara_description: String,
+    pub chara_text: String,
+    pub chara_text_roma: String,
+    pub skill_text: String,
+}
+
+impl TextFiles {
+    pub fn resolve(extraction_root: &Path, language: &str) -> Result<TextFiles, ResolveError> {
+        let folder = Path::new(TEXT_ROOT_PATH).join(language);
+        let mut files = TEXT_RULES.resolve(extraction_root, &folder)?.into_result()?;
+
+        Ok(TextFiles {
+            chara_add_info: files.take("chara_add_info"),
+            chara_description: files.take("chara_description"),
+            chara_text: files.take("chara_text"),
+            chara_text_roma: files.take("chara_text_roma"),
+            skill_text: files.take("skill_text"),
+        })
+    }
+
+    pub fn file_names(&self) -> [&str; 5] {
+        [&self.chara_add_info, &self.chara_description, &self.chara_text, &self.chara_text_roma, &self.skill_text]
+    }
+}
 
+/// Parses the text files of every language in parallel.
+pub fn load_text_data(extraction_path: &Path, files: &BTreeMap<String, TextFiles>, report: &mut ValidationReport) -> Result<HashMap<String, LocalizedText>, MiningError> {
     let root_path = extraction_path.join(TEXT_ROOT_PATH);
 
-    let mut databases: Vec<TextDatabase> = text_database_connections.into_par_iter().map(|(language, conn)| {
-        TextDatabase::init( 
-            conn,
-            parse_gamefile(&root_path.join(language).join(&requested_files[language]["chara_text"])).unwrap(), 
-            parse_gamefile(&root_path.join(language).join(&requested_files[language]["chara_text_roma"])).unwrap(), 
-            parse_gamefile(&root_path.join(language).join(&requested_files[language]["chara_description"])).unwrap(),
-            parse_gamefile(&root_path.join(language).join(&requested_files[language]["chara_add_info"])).unwrap(),
-            parse_gamefile(&root_path.join(language).join(&requested_files[language]["skill_text"])).unwrap()
-        )
-    }).collect();
+    let languages: Vec<(String, LocalizedText, ValidationReport)> = files.par_iter()
+        .map(|(language, files)| {
+            let mut language_report = ValidationReport::new(report.strict);
+            let text = LocalizedText::load(&root_path.join(language), files, &mut language_report)?;
+            Ok((language.clone(), text, language_report))
+        })
+        .collect::<Result<_, MiningError>>()?;
+
+    let mut text = HashMap::with_capacity(languages.len());
+    for (language, localized_text, language_report) in languages {
+        text.insert(language, localized_text);
+        report.extend(language_report);
+    }
+
+    Ok(text)
+}
+
+/// Writes the texts requested by the other writer threads into the database
+/// of each language, paired with its texts, until every request channel is closed.
+pub fn write_text_data(text_databases: Vec<(Connection, &LocalizedText)>, char_name_req_rx: Receiver<(i32, i32)>, skill_name_req_rx: Receiver<(i32, i32)>) -> rusqlite::Result<()> {
+    let mut databases: Vec<TextDatabase> = text_databases.into_par_iter()
+        .map(|(conn, text)| TextDatabase::init(conn, text))
+        .collect::<rusqlite::Result<_>>()?;
 
     let mut char_requests = Vec::with_capacity(1000);
 
@@ -59,10 +173,10 @@ pub fn populate_text_data(extraction_path: &Path, text_database_connections: Has
         char_requests.push(char_request);
 
         if char_requests.len() >= 1000 {
-            databases.par_iter_mut().for_each(|d| {
-                d.write_character(&char_requests);
-                d.write_character_roma(&char_requests);
-            });
+            databases.par_iter_mut().try_for_each(|d| {
+                d.write_character(&char_requests)?;
+                d.write_character_roma(&char_requests)
+            })?;
 
             char_requests.clear();
         }
@@ -70,11 +184,28 @@ pub fn populate_text_data(extraction_path: &Path, text_database_connections: Has
 
     // Flush remaining requests
     if !char_requests.is_empty() {
-        databases.par_iter_mut().for_each(|d| {
-            d.write_character(&char_requests);
-            d.write_character_roma(&char_requests);
-        });
+        databases.par_iter_mut().try_for_each(|d| {
+            d.write_character(&char_requests)?;
+            d.write_character_roma(&char_requests)
+        })?;
+    }
+
+    // The skill requests are handled once all the character ones are written
+    let mut skill_requests = Vec::with_capacity(1000);
+
+    while let Ok(skill_request) = skill_name_req_rx.recv() {
+        skill_requests.push(skill_request);
+
+        if skill_requests.len() >= 1000 {
+            databases.par_iter_mut().try_for_each(|d| d.write_skill(&skill_requests))?;
+
+            skill_requests.clear();
+        }
+    }
+
+    if !skill_requests.is_empty() {
+        databases.par_iter_mut().try_for_each(|d| d.write_skill(&skill_requests))?;
     }
 
-    println!("[TEXT]: {} requested name(s) not found.", databases[0].get_missing_names());
+    Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,110 @@
+use std::{collections::{BTreeMap, BTreeSet, HashMap}, fs::File, io::{self, BufWriter}, path::Path};
+
+use serde::Serialize;
+
+use crate::GameData;
+
+use super::LocalizedText;
+
+/// The number of missing IDs printed in the console for each kind of text, the others only being in the coverage file
+const PRINTED_MISSING_IDS: usize = 10;
+
+/// Which of the texts referenced by the extracted data are present in each language.
+#[derive(Debug, Default, Serialize)]
+pub struct CoverageReport {
+    pub languages: BTreeMap<String, LanguageCoverage>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct LanguageCoverage {
+    pub names: TextCoverage,
+    pub roma_names: TextCoverage,
+    pub descriptions: TextCoverage,
+    pub skill_names: TextCoverage,
+    pub skill_descriptions: TextCoverage,
+}
+
+/// The coverage of a kind of text, every referenced ID being counted once.
+#[derive(Debug, Default, Serialize)]
+pub struct TextCoverage {
+    pub present: usize,
+    /// The referenced IDs without a text, sorted
+    pub missing: Vec<i32>,
+}
+
+impl TextCoverage {
+    fn new(ids: &BTreeSet<i32>, texts: &HashMap<i32, String>) -> TextCoverage {
+        let missing: Vec<i32> = ids.iter().filter(|id| !texts.contains_key(id)).copied().collect();
+
+        TextCoverage { present: ids.len() - missing.len(), missing }
+    }
+
+    pub fn total(&self) -> usize {
+        self.present + self.missing.len()
+    }
+}
+
+impl LanguageCoverage {
+    /// The kinds of text, with the names they are printed with
+    pub fn kinds(&self) -> [(&'static str, &TextCoverage); 5] {
+        [
+            ("names", &self.names),
+            ("roma names", &self.roma_names),
+            ("descriptions", &self.descriptions),
+            ("skill names", &self.skill_names),
+            ("skill descriptions", &self.skill_descriptions),
+        ]
+    }
+}
+
+impl CoverageReport {
+    pub fn new(game_data: &GameData) -> CoverageReport {
+        let name_ids: BTreeSet<i32> = game_data.characters.iter().map(|c| c.name_id).collect();
+        let description_ids: BTreeSet<i32> = game_data.characters.iter().map(|c| c.description_id).collect();
+        let skill_name_ids: BTreeSet<i32> = game_data.skills.iter().map(|s| s.name_id).collect();
+        let skill_description_ids: BTreeSet<i32> = game_data.skills.iter().map(|s| s.description_id).collect();
+
+        let coverage = |text: &LocalizedText| LanguageCoverage {
+            names: TextCoverage::new(&name_ids, &text.chara_names),
+            roma_names: TextCoverage::new(&name_ids, &text.chara_roma_names),
+            descriptions: TextCoverage::new(&description_ids, &text.chara_descriptions),
+            skill_names: TextCoverage::new(&skill_name_ids, &text.skill_names),
+            skill_descriptions: TextCoverage::new(&skill_description_ids, &text.skill_descriptions),
+        };
+
+        CoverageReport {
+            languages: game_data.text.iter()
+                .map(|(language, text)| (language.clone(), coverage(text)))
+                .collect(),
+        }
+    }
+
+    pub fn print_summary(&self) {
+        for (language, coverage) in &self.languages {
+            let counts: Vec<String> = coverage.kinds().iter()
+                .map(|(kind, text)| format!("{kind} {}/{}", text.present, text.total()))
+                .collect();
+
+            println!("[COVERAGE]: {language}: {}.", counts.join(", "));
+
+            for (kind, text) in coverage.kinds() {
+                if text.missing.is_empty() {
+                    continue;
+                }
+
+                let ids: Vec<String> = text.missing.iter().take(PRINTED_MISSING_IDS).map(|id| id.to_string()).collect();
+                let more = match text.missing.len().checked_sub(PRINTED_MISSING_IDS) {
+                    Some(more) if more > 0 => format!(" ... and {more} more, see the coverage file"),
+                    _ => String::new(),
+                };
+
+                println!("  missing {kind}: {}{more}", ids.join(", "));
+            }
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self).map_err(io::Error::other)
+    }
+}
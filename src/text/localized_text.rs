@@ -0,0 +1,150 @@
+use std::{collections::HashMap, path::Path};
+
+use serde::Serialize;
+
+use super::TextFiles;
+use crate::{common::{GameFile, GameRow}, schema, validation::{MiningError, MiningErrorKind, ValidationReport}};
+
+/// Every text of a single language used by the extracted data, by text ID.
+#[derive(Debug, Default, Serialize)]
+pub struct LocalizedText {
+    pub chara_names: HashMap<i32, String>,
+    /// The alternative forms of the character names (nicknames, grammatical
+    /// variants...), by name ID and then by variant index
+    pub chara_name_variants: HashMap<i32, HashMap<i32, String>>,
+    pub chara_roma_names: HashMap<i32, String>,
+    pub chara_descriptions: HashMap<i32, String>,
+    pub series_names: HashMap<i32, String>,
+    /// The alternative forms of the names of chara_add_info, by text ID and then by variant index
+    pub add_info_name_variants: HashMap<i32, HashMap<i32, String>>,
+    /// The longer texts of chara_add_info, when the file has some
+    pub add_info_texts: HashMap<i32, String>,
+    pub skill_names: HashMap<i32, String>,
+    pub skill_descriptions: HashMap<i32, String>,
+}
+
+impl LocalizedText {
+    /// Parses the text files of a language, `language_path` being the
+    /// folder holding them and `files` their resolved names.
+    pub fn load(language_path: &Path, files: &TextFiles, report: &mut ValidationReport) -> Result<LocalizedText, MiningError> {
+        let chara_text = GameFile::open(&language_path.join(&files.chara_text))?;
+        let chara_text_roma = GameFile::open(&language_path.join(&files.chara_text_roma))?;
+        let chara_description = GameFile::open(&language_path.join(&files.chara_description))?;
+        let chara_add_info = GameFile::open(&language_path.join(&files.chara_add_info))?;
+        let skill_text = GameFile::open(&language_path.join(&files.skill_text))?;
+
+        // The nouns are stored with their ID in column 0 and their text in column 5,
+        // column 1 being different from 0 when texts are alternatives of the main one
+        let noun = |row: &GameRow| -> Result<(i32, i32, String), MiningError> { Ok((row.int("id")?, row.int("variant")?, row.string("text")?)) };
+
+        // The longer texts only have their ID in column 0 and their text in column 2
+        let text = |row: &GameRow| -> Result<(i32, String), MiningError> { Ok((row.int("id")?, row.string("text")?)) };
+
+        // Computing the character hash table
+        let chara_table = chara_text.table(&schema::NOUN_INFO, report)?;
+        
+        let mut chara_names = HashMap::with_capacity(chara_table.len());
+        let mut chara_name_variants: HashMap<i32, HashMap<i32, String>> = HashMap::new();
+        for row in chara_table.rows() {
+            let Some((index, variant, string)) = report.record(noun(&row))? else { continue };
+
+            match variant {
+                0 => insert_text(&mut chara_names, index, string, &row, None, report),
+                _ => insert_text(chara_name_variants.entry(index).or_default(), variant, string, &row, Some(index), report),
+            }
+        };
+
+        // Computing the character roma hash table
+        let chara_roma_table = chara_text_roma.table(&schema::NOUN_INFO, report)?;
+
+        let mut chara_roma_names = HashMap::with_capacity(chara_roma_table.len());
+        for row in chara_roma_table.rows() {
+            let Some((index, variant, string)) = report.record(noun(&row))? else { continue };
+
+            if variant == 0 {
+                insert_text(&mut chara_roma_names, index, string, &row, None, report);
+            }
+        }
+
+        // Computing the character description table
+        let chara_desc_table = chara_description.table(&schema::TEXT_INFO, report)?;
+
+        let mut chara_descriptions = HashMap::with_capacity(chara_desc_table.len());
+        for row in chara_desc_table.rows() {
+            let Some((index, string)) = report.record(text(&row))? else { continue };
+
+            insert_text(&mut chara_descriptions, index, string, &row, None, report);
+        }
+
+        // Computing the skill name table
+        let skill_name_table = skill_text.table(&schema::NOUN_INFO, report)?;
+
+        let mut skill_names = HashMap::with_capacity(skill_name_table.len());
+        for row in skill_name_table.rows() {
+            let Some((index, variant, string)) = report.record(noun(&row))? else { continue };
+
+            if variant == 0 { // Same as for the character names
+                insert_text(&mut skill_names, index, string, &row, None, report);
+            }
+        }
+
+        // Computing the skill description table
+        let skill_desc_table = skill_text.table(&schema::TEXT_INFO, report)?;
+
+        let mut skill_descriptions = HashMap::with_capacity(skill_desc_table.len());
+        for row in skill_desc_table.rows() {
+            let Some((index, string)) = report.record(text(&row))? else { continue };
+
+            insert_text(&mut skill_descriptions, index, string, &row, None, report);
+        }
+
+        // Computing the series table, which also holds the other names of chara_add_info
+        let series_table = chara_add_info.table(&schema::NOUN_INFO, report)?;
+
+        let mut series_names = HashMap::with_capacity(series_table.len());
+        let mut add_info_name_variants: HashMap<i32, HashMap<i32, String>> = HashMap::new();
+        for row in series_table.rows() {
+            let Some((index, variant, name)) = report.record(noun(&row))? else { continue };
+
+            match variant {
+                0 => insert_text(&mut series_names, index, name, &row, None, report),
+                _ => insert_text(add_info_name_variants.entry(index).or_default(), variant, name, &row, Some(index), report),
+            }
+        }
+
+        // Not every version of chara_add_info has longer texts
+        let mut add_info_texts = HashMap::new();
+        if chara_add_info.has_table(&schema::TEXT_INFO) {
+            for row in chara_add_info.table(&schema::TEXT_INFO, report)?.rows() {
+                let Some((index, string)) = report.record(text(&row))? else { continue };
+
+                insert_text(&mut add_info_texts, index, string, &row, None, report);
+            }
+        }
+
+        Ok(LocalizedText { 
+            chara_names, 
+            chara_name_variants,
+            chara_roma_names, 
+            chara_descriptions, 
+            series_names, 
+            add_info_name_variants,
+            add_info_texts,
+            skill_names, 
+            skill_descriptions 
+        })
+    }
+}
+
+/// Keeps the last text of a key, the rows defining it again being recorded as warnings.
+/// For the name variants, `key` is the variant index and `variant_of` the name ID.
+fn insert_text(texts: &mut HashMap<i32, String>, key: i32, text: String, row: &GameRow, variant_of: Option<i32>, report: &mut ValidationReport) {
+    if texts.insert(key, text).is_some() {
+        let kind = match variant_of {
+            Some(id) => MiningErrorKind::DuplicateText { id, variant: Some(key) },
+            None => MiningErrorKind::DuplicateText { id: key, variant: None },
+        };
+
+        report.warn(row.error(Some("id"), kind));
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::{BTreeSet, HashMap};
+
+use rusqlite::{Connection, Result, params};
+
+use crate::{Character, Skill};
+
+use super::LocalizedText;
+
+/// Selects the texts a table is filled from
+type Texts = fn(&LocalizedText) -> &HashMap<i32, String>;
+
+/// Selects the alternative forms of names a table is filled from
+type Variants = fn(&LocalizedText) -> &HashMap<i32, HashMap<i32, String>>;
+
+/// The tables holding a single text per ID, with their text column and the texts they are filled from
+const TEXT_TABLES: [(&str, &str, Texts); 5] = [
+    ("character_names",         "name",         |text| &text.chara_names),
+    ("character_names_roma",    "name",         |text| &text.chara_roma_names),
+    ("character_descriptions",  "description",  |text| &text.chara_descriptions),
+    ("series_names",            "name",         |text| &text.series_names),
+    ("add_info_texts",          "text",         |text| &text.add_info_texts),
+];
+
+/// The tables holding the alternative forms of names, by ID and variant index
+const VARIANT_TABLES: [(&str, Variants); 2] = [
+    ("character_name_variants", |text| &text.chara_name_variants),
+    ("add_info_name_variants",  |text| &text.add_info_name_variants),
+];
+
+/// Writes the texts of every language into a single database, keyed by ID and language.
+/// It holds the same texts as the databases of each language, so that several
+/// languages can be read side by side without attaching databases.
+pub fn write_merged_text_data(conn: &mut Connection, text: &HashMap<String, LocalizedText>, characters: &[Character], skills: &[Skill]) -> Result<()> {
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+    conn.pragma_update(None, "foreign_keys", "ON")?;
+
+    initialize_database(conn)?;
+
+    let mut languages: Vec<&String> = text.keys().collect();
+    languages.sort();
+
+    // Like the databases of each language, we only keep the texts of the extracted data
+    let name_ids: BTreeSet<i32> = characters.iter().map(|c| c.name_id).collect();
+    let description_ids: BTreeSet<i32> = characters.iter().map(|c| c.description_id).collect();
+
+    let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Exclusive)?;
+
+    {
+        let mut language_stmt = tx.prepare_cached("INSERT INTO languages (code) VALUES (?1)")?;
+        let mut skill_stmt = tx.prepare_cached(
+            "INSERT INTO skill_names (id, language, name, description)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(id, language) DO NOTHING"
+        )?;
+
+        for language in languages {
+            language_stmt.execute(params![language])?;
+
+            let localized_text = &text[language];
+
+            for (table, column, texts) in TEXT_TABLES {
+                let mut stmt = tx.prepare_cached(&format!("INSERT INTO {table} (id, language, {column}) VALUES (?1, ?2, ?3)"))?;
+
+                let ids: Option<&BTreeSet<i32>> = match table {
+                    "character_names" | "character_names_roma" => Some(&name_ids),
+                    "character_descriptions" => Some(&description_ids),
+                    _ => None,
+                };
+
+                for (id, text) in texts(localized_text) {
+                    if ids.is_none_or(|ids| ids.contains(id)) {
+                        stmt.execute(params![id, language, text])?;
+                    }
+                }
+            }
+
+            for (table, variants) in VARIANT_TABLES {
+                let mut stmt = tx.prepare_cached(&format!("INSERT INTO {table} (id, language, variant, name) VALUES (?1, ?2, ?3, ?4)"))?;
+
+                let ids = (table == "character_name_variants").then_some(&name_ids);
+
+                for (id, names) in variants(localized_text) {
+                    if ids.is_none_or(|ids| ids.contains(id)) {
+                        for (variant, name) in names {
+                            stmt.execute(params![id, language, variant, name])?;
+                        }
+                    }
+                }
+            }
+
+            for skill in skills {
+                if let Some(name) = localized_text.skill_names.get(&skill.name_id) {
+                    skill_stmt.execute(params![skill.name_id, language, name, localized_text.skill_descriptions.get(&skill.description_id)])?;
+                }
+            }
+        }
+    }
+
+    tx.commit()
+}
+
+fn initialize_database(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS languages (
+            code TEXT PRIMARY KEY
+        )",
+        ()
+    )?;
+
+    for (table, column, _) in TEXT_TABLES {
+        conn.execute(
+            &format!("CREATE TABLE IF NOT EXISTS {table} (
+                id INTEGER NOT NULL,
+                language TEXT NOT NULL REFERENCES languages(code),
+                {column} TEXT NOT NULL,
+                PRIMARY KEY (id, language)
+            )"),
+            ()
+        )?;
+    }
+
+    for (table, _) in VARIANT_TABLES {
+        conn.execute(
+            &format!("CREATE TABLE IF NOT EXISTS {table} (
+                id INTEGER NOT NULL,
+                language TEXT NOT NULL REFERENCES languages(code),
+                variant INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                PRIMARY KEY (id, language, variant)
+            )"),
+            ()
+        )?;
+    }
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS skill_names (
+            id INTEGER NOT NULL,
+            language TEXT NOT NULL REFERENCES languages(code),
+            name TEXT NOT NULL,
+            description TEXT,
+            PRIMARY KEY (id, language)
+        )",
+        ()
+    )?;
+
+    // When mining incrementally, only the content of the tables is replaced
+    for table in TEXT_TABLES.map(|(table, _, _)| table).into_iter().chain(VARIANT_TABLES.map(|(table, _)| table)) {
+        conn.execute(&format!("DELETE FROM {table}"), ())?;
+    }
+
+    conn.execute_batch(
+        "DELETE FROM skill_names;
+        DELETE FROM languages;"
+    )
+}
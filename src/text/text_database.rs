@@ -1,213 +1,250 @@
-use std::{collections::HashMap};
+use std::collections::HashMap;
 
-use ievr_cfg_bin_editor_core::Database;
-use rusqlite::{Connection, params};
+use rusqlite::{Connection, Result, params};
 
-use crate::common::{parse_int_value, parse_string_value};
+use super::LocalizedText;
 
-pub struct TextDatabase {
+pub struct TextDatabase<'a> {
     conn: Connection,
-    chara_names: HashMap<i32, String>,
-    chara_roma_names: HashMap<i32, String>,
-    chara_descriptions: HashMap<i32, String>,
-    // skill_text: HashMap<i32, (String, String)>,
-
-    missing_character_names: u32,
+    text: &'a LocalizedText,
 }
 
-impl TextDatabase {
-    pub fn init(mut conn: Connection, 
-        chara_text: Database, 
-        chara_text_roma: Database, 
-        chara_description: Database, 
-        chara_add_info: Database,
-        _skill_text: Database
-    ) -> TextDatabase {
-        conn.pragma_update(None, "journal_mode", "WAL").unwrap();
-        conn.pragma_update(None, "synchronous", "NORMAL").unwrap();
-
-        Self::initialize_database(&conn);
-
-        // Computing the character hash table
-        let chara_table = chara_text.table("NOUN_INFO").unwrap();
-        let rows = chara_table.rows();
-        
-        let mut chara_names = HashMap::with_capacity(rows.len());
-        for row in rows {
-            let index = parse_int_value(&row.values[0][0]);
-            let string = parse_string_value(&row.values[5][0]);
-
-            if parse_int_value(&row.values[1][0]) == 0 { // This value is different from 0 when texts are alternatives of the main one
-                if chara_names.insert(index, string).is_some() {
-                    println!("Text index {index} in double");
-                }
-            }
-        };
-
-        // Computing the character roma hash table
-        let chara_roma_table = chara_text_roma.table("NOUN_INFO").unwrap();
-        let rows = chara_roma_table.rows();
-
-        let mut chara_roma_names = HashMap::with_capacity(rows.len());
-        for row in rows {
-            let index = parse_int_value(&row.values[0][0]);
-            let string = parse_string_value(&row.values[5][0]);
-
-            if parse_int_value(&row.values[1][0]) == 0 { // This value is different from 0 when texts are alternatives of the main one
-                if chara_roma_names.insert(index, string).is_some() {
-                    println!("Text index {index} in double");
-                }
-            }
-        }
-
-        // Computing the character description table
-        let chara_desc_table = chara_description.table("TEXT_INFO").unwrap();
-        let rows = chara_desc_table.rows();
-
-        let mut chara_descriptions = HashMap::with_capacity(rows.len());
-        for row in rows {
-            let index = parse_int_value(&row.values[0][0]);
-            let string = parse_string_value(&row.values[2][0]);
+impl<'a> TextDatabase<'a> {
+    pub fn init(mut conn: Connection, text: &'a LocalizedText) -> Result<TextDatabase<'a>> {
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
 
-            if chara_descriptions.insert(index, string).is_some() {
-                println!("Character description {index} in double");
-            }
-        }
+        Self::initialize_database(&conn)?;
 
         // Inserting the series' names into the database
-        let series_table = chara_add_info.table("NOUN_INFO").unwrap();
-        Self::insert_series(&mut conn, series_table);
+        Self::insert_series(&mut conn, &text.series_names)?;
+
+        // The rest of chara_add_info is not referenced by the extracted data, so it is kept whole
+        Self::insert_variants(&mut conn, "add_info_name_variants", &text.add_info_name_variants)?;
+        Self::insert_texts(&mut conn, "add_info_texts", &text.add_info_texts)?;
         
-        TextDatabase { conn, chara_names, chara_roma_names, chara_descriptions, missing_character_names: 0 }
+        Ok(TextDatabase { conn, text })
     }
 
-    pub fn write_character(&mut self, index_batch: &Vec<(i32, i32)>) {
-        let tx = self.conn.transaction_with_behavior(rusqlite::TransactionBehavior::Exclusive).unwrap();
+    pub fn write_character(&mut self, index_batch: &Vec<(i32, i32)>) -> Result<()> {
+        let tx = self.conn.transaction_with_behavior(rusqlite::TransactionBehavior::Exclusive)?;
 
         {
             let mut name_stmt = tx.prepare_cached("
                 INSERT INTO character_names (id, name) 
                 VALUES (?1, ?2) 
                 ON CONFLICT(id) DO NOTHING
-            ").unwrap();
+            ")?;
+
+            let mut variant_stmt = tx.prepare_cached("
+                INSERT INTO character_name_variants (id, variant, name) 
+                VALUES (?1, ?2, ?3) 
+                ON CONFLICT(id, variant) DO NOTHING
+            ")?;
 
             let mut desc_stmt = tx.prepare_cached("
                 INSERT INTO character_descriptions (id, description) 
                 VALUES (?1, ?2) 
                 ON CONFLICT(id) DO NOTHING
-            ").unwrap();
+            ")?;
             
             for (chara_index, chara_desc) in index_batch {
-                match self.chara_names.get(chara_index) {
-                    Some(name) => { name_stmt.execute(params![chara_index, name]).unwrap(); },
-                    None => self.missing_character_names += 1,
+                if let Some(name) = self.text.chara_names.get(chara_index) {
+                    name_stmt.execute(params![chara_index, name])?;
                 } 
 
-                if let Some(desc) = self.chara_descriptions.get(chara_desc) {
-                    desc_stmt.execute(params![chara_desc, desc]).unwrap();
-                } 
+                for (variant, name) in self.text.chara_name_variants.get(chara_index).into_iter().flatten() {
+                    variant_stmt.execute(params![chara_index, variant, name])?;
+                }
+
+                if let Some(desc) = self.text.chara_descriptions.get(chara_desc) {
+                    desc_stmt.execute(params![chara_desc, desc])?;
+                }
             }
         }
         
-        tx.commit().unwrap();
+        tx.commit()
     }
 
-    pub fn write_character_roma(&mut self, index_batch: &Vec<(i32, i32)>) {
-        let tx = self.conn.transaction_with_behavior(rusqlite::TransactionBehavior::Exclusive).unwrap();
+    pub fn write_character_roma(&mut self, index_batch: &Vec<(i32, i32)>) -> Result<()> {
+        let tx = self.conn.transaction_with_behavior(rusqlite::TransactionBehavior::Exclusive)?;
 
         {
             let mut stmt = tx.prepare_cached("
                 INSERT INTO character_names_roma (id, name) 
                 VALUES (?1, ?2) 
                 ON CONFLICT(id) DO NOTHING
-            ").unwrap();
+            ")?;
             
             for (chara_index, _) in index_batch {
-                if let Some(name) = self.chara_roma_names.get(chara_index) {
-                    stmt.execute(params![chara_index, name]).unwrap();
+                if let Some(name) = self.text.chara_roma_names.get(chara_index) {
+                    stmt.execute(params![chara_index, name])?;
                 }
             }
         }
         
-        tx.commit().unwrap();
+        tx.commit()
     }
 
-    #[allow(dead_code)]
-    pub fn write_skill(&mut self, _index_batch: &Vec<i32>) {
-        let _sql = "INSERT INTO skill_names (id, name, description) 
-            VALUES (?1, ?2, ?3)
-            ON CONFLICT(id) DO NOTHING";
+    pub fn write_skill(&mut self, index_batch: &Vec<(i32, i32)>) -> Result<()> {
+        let tx = self.conn.transaction_with_behavior(rusqlite::TransactionBehavior::Exclusive)?;
+
+        {
+            let mut stmt = tx.prepare_cached("
+                INSERT INTO skill_names (id, name, description) 
+                VALUES (?1, ?2, ?3)
+                ON CONFLICT(id) DO NOTHING
+            ")?;
+
+            for (skill_index, skill_desc) in index_batch {
+                if let Some(name) = self.text.skill_names.get(skill_index) {
+                    stmt.execute(params![skill_index, name, self.text.skill_descriptions.get(skill_desc)])?; 
+                }
+            }
+        }
+
+        tx.commit()
     }
     
-    pub fn get_missing_names(&self) -> u32 {
-        self.missing_character_names
-    }
-
-    fn initialize_database(conn: &Connection) {
+    fn initialize_database(conn: &Connection) -> Result<()> {
         conn.execute(
-                "CREATE TABLE character_names (
+                "CREATE TABLE IF NOT EXISTS character_names (
                 id INTEGER PRIMARY KEY,
                 name TEXT NOT NULL
             )", 
             ()
-        ).unwrap();
+        )?;
 
+        // The alternative forms of the names, like nicknames, next to the main one
         conn.execute(
-                "CREATE TABLE character_names_roma (
+                "CREATE TABLE IF NOT EXISTS character_name_variants (
+                id INTEGER NOT NULL,
+                variant INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                PRIMARY KEY (id, variant)
+            )", 
+            ()
+        )?;
+
+        conn.execute(
+                "CREATE TABLE IF NOT EXISTS character_names_roma (
                 id INTEGER PRIMARY KEY,
                 name TEXT NOT NULL
             )", 
             ()
-        ).unwrap();
+        )?;
 
         conn.execute(
-                "CREATE TABLE character_descriptions (
+                "CREATE TABLE IF NOT EXISTS character_descriptions (
                 id INTEGER PRIMARY KEY,
                 description TEXT NOT NULL
             )", 
             ()
-        ).unwrap();
+        )?;
 
         conn.execute(
-                "CREATE TABLE series_names (
+                "CREATE TABLE IF NOT EXISTS series_names (
                 id INTEGER PRIMARY KEY,
                 name TEXT NOT NULL
             )", 
             ()
-        ).unwrap();
+        )?;
+
+        conn.execute(
+                "CREATE TABLE IF NOT EXISTS add_info_name_variants (
+                id INTEGER NOT NULL,
+                variant INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                PRIMARY KEY (id, variant)
+            )", 
+            ()
+        )?;
+
+        conn.execute(
+                "CREATE TABLE IF NOT EXISTS add_info_texts (
+                id INTEGER PRIMARY KEY,
+                text TEXT NOT NULL
+            )", 
+            ()
+        )?;
 
         conn.execute(
-                "CREATE TABLE skill_names (
+                "CREATE TABLE IF NOT EXISTS skill_names (
                 id INTEGER PRIMARY KEY,
                 name TEXT NOT NULL,
-                description TEXT NOT NULL
+                description TEXT
             )", 
             ()
-        ).unwrap();
+        )?;
+
+        // When mining incrementally, only the content of the tables is replaced
+        conn.execute_batch(
+            "DELETE FROM character_names;
+            DELETE FROM character_name_variants;
+            DELETE FROM character_names_roma;
+            DELETE FROM character_descriptions;
+            DELETE FROM series_names;
+            DELETE FROM add_info_name_variants;
+            DELETE FROM add_info_texts;
+            DELETE FROM skill_names;"
+        )
     }
 
-    fn insert_series(conn: &mut Connection, series_table: &ievr_cfg_bin_editor_core::Table) {
-        let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Exclusive).unwrap();
+    fn insert_series(conn: &mut Connection, series_names: &HashMap<i32, String>) -> Result<()> {
+        let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Exclusive)?;
 
         {
             let mut stmt = tx.prepare(
                 "INSERT INTO series_names (id, name)
                 VALUES (?1, ?2);"
-            ).unwrap();
+            )?;
 
-            for row in series_table.rows() {
-                let index = parse_int_value(&row.values[0][0]);
-                let name = parse_string_value(&row.values[5][0]);
+            for (index, name) in series_names {
+                stmt.execute(params![index, name])?;
+            }
+        }
+
+        tx.commit()
+    }
+
+    fn insert_variants(conn: &mut Connection, table: &str, variants: &HashMap<i32, HashMap<i32, String>>) -> Result<()> {
+        let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Exclusive)?;
+
+        {
+            let mut stmt = tx.prepare(&format!(
+                "INSERT INTO {table} (id, variant, name)
+                VALUES (?1, ?2, ?3);"
+            ))?;
+
+            for (index, names) in variants {
+                for (variant, name) in names {
+                    stmt.execute(params![index, variant, name])?;
+                }
+            }
+        }
+
+        tx.commit()
+    }
+
+    fn insert_texts(conn: &mut Connection, table: &str, texts: &HashMap<i32, String>) -> Result<()> {
+        let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Exclusive)?;
+
+        {
+            let mut stmt = tx.prepare(&format!(
+                "INSERT INTO {table} (id, text)
+                VALUES (?1, ?2);"
+            ))?;
 
-                stmt.execute(params![index, name]).unwrap();
+            for (index, text) in texts {
+                stmt.execute(params![index, text])?;
             }
         }
 
-        tx.commit().unwrap();
+        tx.commit()
     }
 }
 
 /// SAFETY:
 /// This object must not share connections with other object of
 /// the same type, otherwise race conditions occurr.
-unsafe impl Sync for TextDatabase {}
\ No newline at end of file
+unsafe impl Sync for TextDatabase<'_> {}
\ No newline at end of file
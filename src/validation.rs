@@ -0,0 +1,175 @@
+use std::{error::Error, fs::File, io::{self, BufWriter}, path::Path};
+
+use serde::Serialize;
+
+use crate::schema::ValueType;
+
+/// The number of anomalies printed in the console, the others only being in the report file
+const PRINTED_ANOMALIES: usize = 20;
+
+/// An anomaly found while reading the game files, located as precisely as possible.
+/// It usually means a game update changed the layout of a file.
+#[derive(Debug, Clone, Serialize)]
+pub struct MiningError {
+    /// The name of the game file
+    pub file: String,
+    pub table: Option<&'static str>,
+    pub row: Option<usize>,
+    pub column: Option<usize>,
+    /// The name given to the column by its schema
+    pub column_name: Option<&'static str>,
+    pub kind: MiningErrorKind,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MiningErrorKind {
+    UnreadableFile { reason: String },
+    /// A file the data can be mined without, whose data is left out
+    MissingOptionalFile { reason: String },
+    MissingTable,
+    MissingColumn,
+    /// A column the schema of the table does not name, which is a bug of the dataminer
+    UnknownColumn { name: String },
+    SchemaMismatch { expected: ValueType, found: ValueType },
+    UnexpectedType { expected: ValueType, found: ValueType },
+    /// An integer too large or negative for what the column holds
+    OutOfRange { value: i32 },
+    UnknownGrowthEntry { main_position: u8, growth_pattern: u8, chara_rank: u8 },
+    UnknownRarity { rarity: i32 },
+    /// A text whose ID, and variant for the nouns, is already defined: the last one is kept
+    DuplicateText { id: i32, variant: Option<i32> },
+    /// A character whose index and rarity are already defined: the last one is kept
+    DuplicateCharacter { index: i32, rarity: i32 },
+    /// A skill whose ID is already defined: the last one is kept
+    DuplicateSkill { id: i32 },
+}
+
+impl MiningError {
+    pub fn new(file: &str, kind: MiningErrorKind) -> MiningError {
+        MiningError { file: file.to_owned(), table: None, row: None, column: None, column_name: None, kind }
+    }
+}
+
+impl Error for MiningError {}
+
+impl std::fmt::Display for MiningError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.file)?;
+
+        if let Some(table) = &self.table { write!(f, ", table {table}")? }
+        if let Some(row) = self.row { write!(f, ", row {row}")? }
+        match (self.column, &self.column_name) {
+            (Some(column), Some(name)) => write!(f, ", column {column} ({name})")?,
+            (Some(column), None) => write!(f, ", column {column}")?,
+            (None, _) => (),
+        }
+
+        write!(f, ": {}", self.kind)
+    }
+}
+
+impl std::fmt::Display for MiningErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnreadableFile { reason } => write!(f, "unreadable file ({reason})"),
+            Self::MissingOptionalFile { reason } => write!(f, "file not mined ({reason})"),
+            Self::MissingTable => write!(f, "missing table"),
+            Self::MissingColumn => write!(f, "missing column"),
+            Self::UnknownColumn { name } => write!(f, "column {name} not described by the table schema"),
+            Self::SchemaMismatch { expected, found } => write!(f, "schema expects {expected} values, found {found}"),
+            Self::UnexpectedType { expected, found } => write!(f, "expected {expected} value, found {found}"),
+            Self::OutOfRange { value } => write!(f, "value {value} out of range"),
+            Self::UnknownGrowthEntry { main_position, growth_pattern, chara_rank } => write!(
+                f, "no growth table entry for position {main_position}, growth pattern {growth_pattern} and rank {chara_rank}"
+            ),
+            Self::UnknownRarity { rarity } => write!(f, "unknown rarity {rarity}"),
+            Self::DuplicateText { id, variant: Some(variant) } => write!(f, "text {id} (variant {variant}) already defined"),
+            Self::DuplicateText { id, variant: None } => write!(f, "text {id} already defined"),
+            Self::DuplicateCharacter { index, rarity } => write!(f, "character {index} (rarity {rarity}) already defined"),
+            Self::DuplicateSkill { id } => write!(f, "skill {id} already defined"),
+        }
+    }
+}
+
+/// Every anomaly found while reading the game files. The rows they were found
+/// in are skipped, so the rest of the data can still be mined.
+#[derive(Debug, Default, Serialize)]
+pub struct ValidationReport {
+    pub anomalies: Vec<MiningError>,
+    /// The oddities that do not stop the mining, like a text, character or skill
+    /// defined twice or a missing optional file. They never fail the strict mode.
+    pub warnings: Vec<MiningError>,
+    /// Fails on the first anomaly instead of recording it
+    #[serde(skip)]
+    pub(crate) strict: bool,
+}
+
+impl ValidationReport {
+    pub fn new(strict: bool) -> ValidationReport {
+        ValidationReport { anomalies: Vec::new(), warnings: Vec::new(), strict }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.anomalies.is_empty()
+    }
+
+    /// Records an anomaly, or returns it in strict mode
+    pub fn push(&mut self, anomaly: MiningError) -> Result<(), MiningError> {
+        if self.strict {
+            return Err(anomaly);
+        }
+
+        self.anomalies.push(anomaly);
+        Ok(())
+    }
+
+    pub fn warn(&mut self, warning: MiningError) {
+        self.warnings.push(warning);
+    }
+
+    pub fn extend(&mut self, other: ValidationReport) {
+        self.anomalies.extend(other.anomalies);
+        self.warnings.extend(other.warnings);
+    }
+
+    /// Returns the value of a row that was read correctly, or records the
+    /// anomaly of one that was not. In strict mode, the anomaly is returned
+    /// instead, so that the parsing stops on it.
+    pub fn record<T>(&mut self, result: Result<T, MiningError>) -> Result<Option<T>, MiningError> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(anomaly) => self.push(anomaly).map(|_| None),
+        }
+    }
+
+    pub fn print_summary(&self) {
+        match self.is_empty() {
+            true => println!("[VALIDATION]: no anomaly found in the game files."),
+            false => {
+                println!("[VALIDATION]: {} anomaly(ies) found in the game files, the affected rows were skipped:", self.anomalies.len());
+                print_errors(&self.anomalies);
+            },
+        }
+
+        if !self.warnings.is_empty() {
+            println!("[VALIDATION]: {} warning(s):", self.warnings.len());
+            print_errors(&self.warnings);
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self).map_err(io::Error::other)
+    }
+}
+
+fn print_errors(errors: &[MiningError]) {
+    for error in errors.iter().take(PRINTED_ANOMALIES) {
+        println!("  {error}");
+    }
+
+    if errors.len() > PRINTED_ANOMALIES {
+        println!("  ... and {} more, see the report file.", errors.len() - PRINTED_ANOMALIES);
+    }
+}
@@ -0,0 +1,197 @@
+use std::{collections::{BTreeMap, BTreeSet}, error::Error, fs, path::Path};
+
+use rusqlite::{Connection, OpenFlags};
+
+use crate::{DATABASES, TEXT_DATABASES_ROOT};
+
+/// The number of failures printed in the console for each check
+const PRINTED_FAILURES: usize = 10;
+
+/// The language the roma names of the other languages are compared to, when it was mined
+const REFERENCE_LANGUAGE: &str = "en";
+
+/// The outcome of an integrity check of the databases.
+#[derive(Debug)]
+pub struct CheckResult {
+    pub name: &'static str,
+    /// What was found wrong, empty when the check passed
+    pub failures: Vec<String>,
+}
+
+/// The outcome of every integrity check, see [`verify_databases`].
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl VerifyReport {
+    pub fn is_success(&self) -> bool {
+        self.checks.iter().all(|check| check.failures.is_empty())
+    }
+
+    /// The number of checks that did not pass
+    pub fn failed(&self) -> usize {
+        self.checks.iter().filter(|check| !check.failures.is_empty()).count()
+    }
+
+    pub fn print_summary(&self) {
+        for check in &self.checks {
+            if check.failures.is_empty() {
+                println!("[VERIFY]: {}: ok", check.name);
+                continue;
+            }
+
+            println!("[VERIFY]: {}: {} failure(s)", check.name, check.failures.len());
+
+            for failure in check.failures.iter().take(PRINTED_FAILURES) {
+                println!("  {failure}");
+            }
+
+            if check.failures.len() > PRINTED_FAILURES {
+                println!("  ... and {} more", check.failures.len() - PRINTED_FAILURES);
+            }
+        }
+    }
+}
+
+/// Runs the integrity checks on the SQLite databases of an output folder,
+/// which must hold `characters.sqlite` and the database of every language.
+pub fn verify_databases(output_folder: &Path) -> Result<VerifyReport, Box<dyn Error>> {
+    let characters = open(&output_folder.join(DATABASES[0]))?;
+    let languages = open_text_databases(&output_folder.join(TEXT_DATABASES_ROOT))?;
+
+    if languages.is_empty() {
+        return Err(format!("no text database in {}", output_folder.join(TEXT_DATABASES_ROOT).display()).into());
+    }
+
+    let name_ids = ids(&characters, "SELECT name_id FROM characters")?;
+    let description_ids = ids(&characters, "SELECT description_id FROM characters")?;
+    let series_ids = ids(&characters, "SELECT series_id FROM characters")?;
+
+    let mut report = VerifyReport::default();
+
+    report.checks.push(CheckResult {
+        name: "every character name resolves in every language",
+        failures: missing_texts(&languages, &name_ids, "SELECT id FROM character_names", "name")?,
+    });
+
+    report.checks.push(CheckResult {
+        name: "every series resolves in every language",
+        failures: missing_texts(&languages, &series_ids, "SELECT id FROM series_names", "series name")?,
+    });
+
+    report.checks.push(CheckResult {
+        name: "the roma names agree across languages",
+        failures: roma_name_differences(&languages)?,
+    });
+
+    let mut orphans = Vec::new();
+    for (language, conn) in &languages {
+        for id in ids(conn, "SELECT id FROM character_descriptions")?.difference(&description_ids) {
+            orphans.push(format!("{language}: description {id} belongs to no character"));
+        }
+    }
+
+    report.checks.push(CheckResult {
+        name: "no orphan description",
+        failures: orphans,
+    });
+
+    report.checks.push(CheckResult {
+        name: "the characters with a known position have non-zero stats",
+        failures: zero_stats(&characters)?,
+    });
+
+    Ok(report)
+}
+
+fn open(path: &Path) -> Result<Connection, Box<dyn Error>> {
+    if !fs::exists(path)? {
+        return Err(format!("missing database {}", path.display()).into());
+    }
+
+    Ok(Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?)
+}
+
+/// Opens the database of every language, sorted by language.
+fn open_text_databases(text_root: &Path) -> Result<Vec<(String, Connection)>, Box<dyn Error>> {
+    let mut languages = Vec::new();
+
+    for entry in fs::read_dir(text_root)? {
+        let path = entry?.path();
+
+        if path.extension().is_some_and(|extension| extension == "sqlite") && let Some(language) = path.file_stem() {
+            languages.push((language.to_string_lossy().into_owned(), open(&path)?));
+        }
+    }
+
+    languages.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    Ok(languages)
+}
+
+fn ids(conn: &Connection, query: &str) -> rusqlite::Result<BTreeSet<i64>> {
+    conn.prepare(query)?
+        .query_map([], |row| row.get(0))?
+        .collect()
+}
+
+/// Lists, for every language, the referenced IDs missing from the IDs returned by `query`.
+fn missing_texts(languages: &[(String, Connection)], referenced: &BTreeSet<i64>, query: &str, kind: &str) -> rusqlite::Result<Vec<String>> {
+    let mut failures = Vec::new();
+
+    for (language, conn) in languages {
+        for id in referenced.difference(&ids(conn, query)?) {
+            failures.push(format!("{language}: {kind} {id} missing"));
+        }
+    }
+
+    Ok(failures)
+}
+
+/// The romanized names do not depend on the language, so every language
+/// must hold the same ones as the reference language.
+fn roma_name_differences(languages: &[(String, Connection)]) -> rusqlite::Result<Vec<String>> {
+    let roma_names = |conn: &Connection| -> rusqlite::Result<BTreeMap<i64, String>> {
+        conn.prepare("SELECT id, name FROM character_names_roma")?
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect()
+    };
+
+    let (reference_language, reference_conn) = languages.iter()
+        .find(|(language, _)| language == REFERENCE_LANGUAGE)
+        .unwrap_or(&languages[0]);
+    let reference = roma_names(reference_conn)?;
+
+    let mut failures = Vec::new();
+
+    for (language, conn) in languages.iter().filter(|(language, _)| language != reference_language) {
+        let names = roma_names(conn)?;
+        let ids: BTreeSet<&i64> = reference.keys().chain(names.keys()).collect();
+
+        for id in ids {
+            match (reference.get(id), names.get(id)) {
+                (Some(expected), Some(name)) if expected == name => (),
+                (Some(expected), Some(name)) => failures.push(format!("{language}: roma name {id} is \"{name}\", \"{expected}\" in {reference_language}")),
+                (Some(_), None) => failures.push(format!("{language}: roma name {id} missing, present in {reference_language}")),
+                (None, _) => failures.push(format!("{language}: roma name {id} missing in {reference_language}")),
+            }
+        }
+    }
+
+    Ok(failures)
+}
+
+fn zero_stats(characters: &Connection) -> rusqlite::Result<Vec<String>> {
+    characters.prepare(
+        "SELECT c.index_id, c.rarity, s.level
+        FROM characters c JOIN character_stats s USING (index_id, rarity)
+        WHERE c.main_position IS NOT NULL AND 0 IN (s.kick, s.control, s.technique, s.pressure, s.physical, s.agility, s.intelligence)
+        ORDER BY c.index_id, c.rarity, s.level"
+    )?
+        .query_map([], |row| {
+            let (index, rarity, level): (i64, i64, i64) = (row.get(0)?, row.get(1)?, row.get(2)?);
+            Ok(format!("character {index}, rarity {rarity}: a stat is 0 at level {level}"))
+        })?
+        .collect()
+}
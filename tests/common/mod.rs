@@ -0,0 +1,308 @@
+//! A generator of synthetic game files, laid out like an extraction folder
+//! of IEVR Toolbox.
+//!
+//! The values are placed at the column indices of the game files, written
+//! here by hand rather than taken from the schemas of the dataminer: a
+//! column moved by mistake in `schema.rs` then makes the tests fail, instead
+//! of being moved in the fixtures as well. The files are written with the
+//! cfg.bin writer of the core crate, so they are read back by the same parser
+//! as the game files.
+//!
+//! Unlike the dataminer, which only reads parsed files, the fixtures build
+//! them: they rely on `write_database` and on the public fields of `Database`,
+//! `Table` and `Row`. These are only used by [`cfg_bin`] and [`row`], the two
+//! functions to update if the core crate changes them.
+
+#![allow(dead_code)] // Each test file only uses some of the fixtures
+
+use std::{fs, path::{Path, PathBuf}};
+
+use ievr_cfg_bin_editor_core::{Database, Row, Table, Value, write_database};
+
+use ievr_dataminer::{CHARA_ROOT_PATH, SKILL_ROOT_PATH, TEXT_ROOT_PATH};
+
+/// The game version in the names of the versioned files
+pub const VERSION: &str = "1.4.0.0";
+
+/// The values of a fixture character, one per rarity.
+#[derive(Debug, Clone)]
+pub struct CharaParam {
+    pub id: i32,
+    pub chara_base_id: i32,
+    pub element: i32,
+    pub main_position: i32,
+    pub alt_position: i32,
+    pub style: i32,
+    pub growth_pattern: i32,
+    pub chara_rank: i32,
+    pub skills: [i32; 6],
+    pub rarity: i32,
+}
+
+/// A temporary extraction folder, removed when dropped.
+pub struct ExtractionFolder {
+    pub root: PathBuf,
+}
+
+impl ExtractionFolder {
+    /// Creates an empty folder, `name` keeping the folders of the tests apart.
+    pub fn new(name: &str) -> ExtractionFolder {
+        let root = std::env::temp_dir().join(format!("ievr_dataminer_{}_{name}", std::process::id()));
+
+        // A previous run may have been interrupted before cleaning up
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+
+        ExtractionFolder { root }
+    }
+
+    /// A folder holding the game files of [`characters`], [`growth_table`],
+    /// [`skills`] and the texts of every language of `languages`.
+    pub fn with_game_files(name: &str, languages: &[&str]) -> ExtractionFolder {
+        let folder = ExtractionFolder::new(name);
+
+        folder.write_characters(&chara_base(), &characters());
+
+        for language in languages {
+            folder.write_texts(language, &chara_names(language), &chara_descriptions(language));
+        }
+
+        folder
+    }
+
+    /// An empty folder to write the outputs in, next to the extraction folder.
+    pub fn output_folder(&self) -> PathBuf {
+        let output = self.root.join("output");
+        fs::create_dir_all(&output).unwrap();
+        output
+    }
+
+    /// Writes a cfg.bin file, `path` being relative to the extraction folder.
+    pub fn write(&self, path: &str, tables: Vec<(&str, Vec<Row>)>) {
+        let path = self.root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+        fs::write(path, cfg_bin(tables)).unwrap();
+    }
+
+    pub fn remove(&self, path: &str) {
+        fs::remove_file(self.root.join(path)).unwrap();
+    }
+
+    /// Writes every character and skill file, with the given chara_base and chara_param rows.
+    pub fn write_characters(&self, chara_base: &[Row], chara_params: &[CharaParam]) {
+        self.write(&format!("{CHARA_ROOT_PATH}chara_base_{VERSION}.cfg.bin"), vec![
+            ("CHARA_BASE_INFO", chara_base.to_vec()),
+        ]);
+        self.write(&format!("{CHARA_ROOT_PATH}chara_param_{VERSION}.cfg.bin"), vec![
+            ("CHARA_PARAM_INFO", chara_params.iter().map(chara_param_row).collect()),
+        ]);
+        self.write(&format!("{CHARA_ROOT_PATH}chara_series_config.cfg.bin"), vec![
+            ("m_charaSeriesInfoList", vec![series_row(SERIES_ID, SERIES_TEXT_ID)]),
+        ]);
+        self.write(&format!("{CHARA_ROOT_PATH}growth_table_config_{VERSION}.cfg.bin"), vec![
+            ("m_growthTableMainList", vec![growth_row(2, 1, 3, GROWTH_LVL50, GROWTH_LVL99)]),
+        ]);
+        self.write(&format!("{SKILL_ROOT_PATH}skill_config_{VERSION}.cfg.bin"), vec![
+            ("SKILL_CONFIG_INFO", skills()),
+        ]);
+    }
+
+    /// Writes the text files of a language, with the given names and descriptions of the characters.
+    pub fn write_texts(&self, language: &str, chara_names: &[Row], chara_descriptions: &[Row]) {
+        let folder = text_folder(language);
+
+        self.write(&format!("{folder}/chara_text.cfg.bin"), vec![
+            ("NOUN_INFO", chara_names.to_vec()),
+        ]);
+        self.write(&format!("{folder}/chara_text_roma.cfg.bin"), vec![
+            ("NOUN_INFO", vec![noun_row(1001, 0, "Mamoru Endou"), noun_row(1002, 0, "Shuuya Gouenji")]),
+        ]);
+        self.write(&format!("{folder}/chara_description_text.cfg.bin"), vec![
+            ("TEXT_INFO", chara_descriptions.to_vec()),
+        ]);
+        self.write(&format!("{folder}/chara_add_info_text.cfg.bin"), vec![
+            ("NOUN_INFO", vec![noun_row(SERIES_TEXT_ID, 0, &format!("Raimon ({language})"))]),
+        ]);
+        self.write(&format!("{folder}/skill_text.cfg.bin"), vec![
+            ("NOUN_INFO", vec![
+                noun_row(5010, 0, &format!("Fire Tornado ({language})")),
+                noun_row(5011, 0, &format!("God Hand ({language})")),
+            ]),
+            ("TEXT_INFO", vec![
+                text_row(6010, &format!("A spinning shot ({language})")),
+                text_row(6011, &format!("A giant hand ({language})")),
+            ]),
+        ]);
+    }
+}
+
+impl Drop for ExtractionFolder {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+pub fn text_folder(language: &str) -> String {
+    Path::new(TEXT_ROOT_PATH).join(language).to_string_lossy().into_owned()
+}
+
+/// The series of every fixture character, and the ID of its name in chara_add_info
+pub const SERIES_ID: u32 = 7;
+pub const SERIES_TEXT_ID: i32 = 3001;
+
+/// The growth table entry of the forwards, the only one of the fixtures
+pub const GROWTH_LVL50: [i32; 7] = [95, 80, 72, 60, 70, 88, 65];
+pub const GROWTH_LVL99: [i32; 7] = [140, 118, 105, 90, 102, 130, 97];
+
+/// Two characters with a valid index, and a third one whose index is 0.
+pub fn chara_base() -> Vec<Row> {
+    vec![
+        chara_base_row(100, 1, 1001, SERIES_ID as i32, 2001),
+        chara_base_row(200, 2, 1002, SERIES_ID as i32, 2002),
+        chara_base_row(300, 0, 1003, SERIES_ID as i32, 2003),
+    ]
+}
+
+/// The first character as a legendary and a hero, and the second one as a
+/// basara without a second technique path, which is filtered out.
+pub fn characters() -> Vec<CharaParam> {
+    let legendary = CharaParam {
+        id: 1000,
+        chara_base_id: 100,
+        element: 3,
+        main_position: 2,
+        alt_position: 3,
+        style: 1,
+        growth_pattern: 1,
+        chara_rank: 3,
+        skills: [10, 11, 10, 11, 10, 11],
+        rarity: 0,
+    };
+
+    vec![
+        legendary.clone(),
+        CharaParam { id: 1005, rarity: 5, skills: [10, 11, 0, 0, 0, 0], ..legendary.clone() },
+        CharaParam { id: 2008, chara_base_id: 200, rarity: 8, skills: [10, 0, 0, 0, 0, 0], ..legendary },
+    ]
+}
+
+/// A placeholder skill with the ID 0, which is not kept, and two skills.
+pub fn skills() -> Vec<Row> {
+    vec![
+        skill_row(0, 0, 0),
+        skill_row(10, 5010, 6010),
+        skill_row(11, 5011, 6011),
+    ]
+}
+
+/// The names of the characters, the first one having a shorter variant.
+pub fn chara_names(language: &str) -> Vec<Row> {
+    vec![
+        noun_row(1001, 0, &format!("Mamoru Endou ({language})")),
+        noun_row(1001, 1, &format!("Endou ({language})")),
+        noun_row(1002, 0, &format!("Shuuya Gouenji ({language})")),
+    ]
+}
+
+pub fn chara_descriptions(language: &str) -> Vec<Row> {
+    vec![
+        text_row(2001, &format!("The captain of Raimon ({language})")),
+        text_row(2002, &format!("The ace striker of Raimon ({language})")),
+    ]
+}
+
+/// The content of a cfg.bin file holding the given tables.
+pub fn cfg_bin(tables: Vec<(&str, Vec<Row>)>) -> Vec<u8> {
+    let database = Database {
+        tables: tables.into_iter()
+            .map(|(name, rows)| Table { name: name.to_owned(), rows })
+            .collect(),
+    };
+
+    write_database(&database)
+}
+
+/// A row whose columns hold the given values, the others holding 0.
+pub fn row(width: usize, values: &[(usize, Value)]) -> Row {
+    let mut row = vec![vec![Value::Int(0)]; width];
+
+    for (index, value) in values {
+        row[*index] = vec![value.clone()];
+    }
+
+    Row { values: row }
+}
+
+pub fn chara_base_row(id: i32, index: i32, name_id: i32, series_id: i32, description_id: i32) -> Row {
+    row(20, &[
+        (0, Value::Int(id)),
+        (2, Value::Int(index)),
+        (3, Value::Int(name_id)),
+        (15, Value::Int(series_id)),
+        (19, Value::Int(description_id)),
+    ])
+}
+
+pub fn chara_param_row(param: &CharaParam) -> Row {
+    let mut values = vec![
+        (0, Value::Int(param.id)),
+        (1, Value::Int(param.chara_base_id)),
+        (2, Value::Int(param.element)),
+        (3, Value::Int(param.main_position)),
+        (4, Value::Int(param.alt_position)),
+        (5, Value::Int(param.style)),
+        (7, Value::Int(param.growth_pattern)),
+        (9, Value::Int(param.chara_rank)),
+        (41, Value::Int(param.rarity)),
+    ];
+    values.extend(param.skills.iter().enumerate().map(|(slot, skill)| (23 + slot, Value::Int(*skill))));
+
+    row(42, &values)
+}
+
+pub fn series_row(series_id: u32, text_id: i32) -> Row {
+    row(3, &[
+        (0, Value::UInt(series_id)),
+        (1, Value::UInt(0)),
+        (2, Value::UInt(text_id as u32)),
+    ])
+}
+
+pub fn growth_row(main_position: u8, growth_pattern: u8, chara_rank: u8, lvl50: [i32; 7], lvl99: [i32; 7]) -> Row {
+    let mut values = vec![
+        (0, Value::Byte(main_position)),
+        (1, Value::Byte(growth_pattern)),
+        (2, Value::Byte(chara_rank)),
+    ];
+    values.extend(lvl50.iter().chain(&lvl99).enumerate().map(|(i, stat)| (3 + i, Value::Int(*stat))));
+
+    row(17, &values)
+}
+
+pub fn skill_row(id: i32, name_id: i32, description_id: i32) -> Row {
+    row(7, &[
+        (0, Value::Int(id)),
+        (1, Value::Int(name_id)),
+        (2, Value::Int(description_id)),
+        (3, Value::Int(3)),
+        (4, Value::Int(1)),
+        (5, Value::Int(50)),
+        (6, Value::Int(20)),
+    ])
+}
+
+pub fn noun_row(id: i32, variant: i32, text: &str) -> Row {
+    row(6, &[
+        (0, Value::Int(id)),
+        (1, Value::Int(variant)),
+        (5, Value::String(text.to_owned())),
+    ])
+}
+
+pub fn text_row(id: i32, text: &str) -> Row {
+    row(3, &[
+        (0, Value::Int(id)),
+        (2, Value::String(text.to_owned())),
+    ])
+}
@@ -0,0 +1,517 @@
+//! End-to-end tests of the mining pipeline.
+//!
+//! Each test writes synthetic game files into a temporary extraction folder
+//! (see `common`), loads them like the game files and checks what ends up in
+//! the SQLite databases. A column read at the wrong index, or a row that
+//! stops being written, fails these tests without needing the game files.
+
+mod common;
+
+use std::{fs, path::Path};
+
+use ievr_cfg_bin_editor_core::Value;
+use rusqlite::{Connection, OptionalExtension, params};
+
+use ievr_dataminer::{
+    DATABASES, GameData, LoadError, LoadOptions, TEXT_DATABASES_ROOT,
+    diff::{DiffSource, GameDiff},
+    file_operations::{FileProblem, create_required_files, prepare_staging_folder},
+    output::{OutputFormat, create_sink},
+    settings::Settings,
+    text::LanguageError,
+    validation::MiningErrorKind,
+    verify::verify_databases,
+};
+
+use common::{CharaParam, ExtractionFolder, chara_base, chara_descriptions, chara_names, characters, noun_row, row};
+
+/// The IDs and types of a row of the characters table
+type CharacterRow = (i32, i32, i32, i32, i32, i32, i32, i32, i32, i32, i32);
+
+fn load(folder: &ExtractionFolder, strict: bool) -> Result<GameData, LoadError> {
+    GameData::load_with(&folder.root, &LoadOptions { strict, languages: Vec::new() })
+}
+
+/// Writes the SQLite databases of the game data into the output folder of `folder`.
+fn write_sqlite(folder: &ExtractionFolder, game_data: &GameData) -> std::path::PathBuf {
+    let output = folder.output_folder();
+
+    let mut languages: Vec<String> = game_data.text.keys().cloned().collect();
+    languages.sort();
+
+    create_required_files(&output, &[OutputFormat::Sqlite], &languages).unwrap();
+    create_sink(OutputFormat::Sqlite, &output, &Settings::default_values()).unwrap()
+        .write(game_data).unwrap();
+
+    output
+}
+
+fn open_text_database(output: &Path, language: &str) -> Connection {
+    Connection::open(output.join(TEXT_DATABASES_ROOT).join(format!("{language}.sqlite"))).unwrap()
+}
+
+fn text(conn: &Connection, query: &str, id: i32) -> Option<String> {
+    conn.query_row(query, params![id], |row| row.get(0)).optional().unwrap()
+}
+
+#[test]
+fn characters_are_mined_into_sqlite() {
+    let folder = ExtractionFolder::with_game_files("characters", &["en"]);
+
+    let game_data = load(&folder, true).unwrap();
+    assert!(game_data.report.is_empty(), "{:?}", game_data.report.anomalies);
+
+    // The basara without a second technique path is filtered out, and the character with the index 0 is not read
+    assert_eq!(game_data.ignored_characters, 1);
+
+    let output = write_sqlite(&folder, &game_data);
+    let conn = Connection::open(output.join(DATABASES[0])).unwrap();
+
+    let characters: Vec<CharacterRow> = conn.prepare(
+        "SELECT index_id, rarity, chara_base_id, chara_param_id, name_id, description_id, series_id, element, main_position, alt_position, style
+        FROM characters ORDER BY index_id, rarity"
+    ).unwrap()
+        .query_map([], |row| Ok((
+            row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?,
+            row.get(6)?, row.get(7)?, row.get(8)?, row.get(9)?, row.get(10)?,
+        ))).unwrap()
+        .collect::<Result<_, _>>().unwrap();
+
+    assert_eq!(characters, [
+        (1, 0, 100, 1000, 1001, 2001, common::SERIES_TEXT_ID, 3, 2, 3, 1),
+        (1, 5, 100, 1005, 1001, 2001, common::SERIES_TEXT_ID, 3, 2, 3, 1),
+    ]);
+
+    // The level 50 stats are multiplied by 1.4, the level 99 ones are the base stats
+    let stats = |rarity: i32, level: i32| -> [i32; 7] {
+        conn.query_row(
+            "SELECT kick, control, technique, pressure, physical, agility, intelligence
+            FROM character_stats WHERE index_id = 1 AND rarity = ?1 AND level = ?2",
+            params![rarity, level],
+            |row| Ok(std::array::from_fn(|i| row.get(i).unwrap()))
+        ).unwrap()
+    };
+
+    for rarity in [0, 5] {
+        assert_eq!(stats(rarity, 50), [133, 112, 100, 84, 98, 123, 91]);
+        assert_eq!(stats(rarity, 99), common::GROWTH_LVL99);
+    }
+
+    // The empty technique path slots of the hero are not stored
+    let skill_count = |rarity: i32| -> i32 {
+        conn.query_row("SELECT COUNT(*) FROM character_skills WHERE index_id = 1 AND rarity = ?1", params![rarity], |row| row.get(0)).unwrap()
+    };
+    assert_eq!(skill_count(0), 6);
+    assert_eq!(skill_count(5), 2);
+}
+
+#[test]
+fn skills_are_mined_into_sqlite() {
+    let folder = ExtractionFolder::with_game_files("skills", &["en"]);
+
+    let game_data = load(&folder, true).unwrap();
+    let output = write_sqlite(&folder, &game_data);
+    let conn = Connection::open(output.join(DATABASES[1])).unwrap();
+
+    // The placeholder skill with the ID 0 is not kept
+    let skills: Vec<(i32, i32, i32, i32, i32, i32, i32)> = conn.prepare(
+        "SELECT id, name_id, description_id, element, kind, power, tp_cost FROM skills ORDER BY id"
+    ).unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?, row.get(6)?))).unwrap()
+        .collect::<Result<_, _>>().unwrap();
+
+    assert_eq!(skills, [(10, 5010, 6010, 3, 1, 50, 20), (11, 5011, 6011, 3, 1, 50, 20)]);
+
+    let texts = open_text_database(&output, "en");
+    let skill_name: (String, Option<String>) = texts.query_row(
+        "SELECT name, description FROM skill_names WHERE id = 5010", [], |row| Ok((row.get(0)?, row.get(1)?))
+    ).unwrap();
+
+    assert_eq!(skill_name, ("Fire Tornado (en)".to_owned(), Some("A spinning shot (en)".to_owned())));
+}
+
+#[test]
+fn skills_are_optional() {
+    let folder = ExtractionFolder::with_game_files("missing_skills", &["en"]);
+    folder.remove(&format!("{}skill_config_{}.cfg.bin", ievr_dataminer::SKILL_ROOT_PATH, common::VERSION));
+
+    // A missing skill file is only a warning, even in strict mode
+    let game_data = load(&folder, true).unwrap();
+    assert_eq!(game_data.characters.len(), 2);
+    assert!(game_data.skills.is_empty());
+
+    let [warning] = game_data.report.warnings.as_slice() else {
+        panic!("expected a single warning, got {:?}", game_data.report.warnings);
+    };
+    assert!(matches!(warning.kind, MiningErrorKind::MissingOptionalFile { .. }), "{warning}");
+
+    let output = write_sqlite(&folder, &game_data);
+    let conn = Connection::open(output.join(DATABASES[1])).unwrap();
+    let skill_count: i32 = conn.query_row("SELECT COUNT(*) FROM skills", [], |row| row.get(0)).unwrap();
+    assert_eq!(skill_count, 0);
+}
+
+#[test]
+fn skill_values_are_checked() {
+    let folder = ExtractionFolder::with_game_files("skill_values", &["en"]);
+
+    // An unknown element is stored as NULL, and a power too large for the game skips the skill
+    let mut skills = common::skills();
+    skills[1].values[3] = vec![Value::Int(9)];
+    skills[2].values[5] = vec![Value::Int(70000)];
+    folder.write(&format!("{}skill_config_{}.cfg.bin", ievr_dataminer::SKILL_ROOT_PATH, common::VERSION), vec![
+        ("SKILL_CONFIG_INFO", skills),
+    ]);
+
+    let game_data = load(&folder, false).unwrap();
+    let output = write_sqlite(&folder, &game_data);
+    let conn = Connection::open(output.join(DATABASES[1])).unwrap();
+
+    let skills: Vec<(i32, Option<i32>)> = conn.prepare("SELECT id, element FROM skills ORDER BY id").unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap()
+        .collect::<Result<_, _>>().unwrap();
+    assert_eq!(skills, [(10, None)]);
+
+    let [anomaly] = game_data.report.anomalies.as_slice() else {
+        panic!("expected a single anomaly, got {:?}", game_data.report.anomalies);
+    };
+    assert_eq!((anomaly.row, anomaly.column_name), (Some(2), Some("power")));
+    assert!(matches!(anomaly.kind, MiningErrorKind::OutOfRange { value: 70000 }));
+}
+
+#[test]
+fn texts_are_mined_for_every_language() {
+    let folder = ExtractionFolder::with_game_files("texts", &["en", "fr"]);
+
+    let game_data = load(&folder, true).unwrap();
+    let output = write_sqlite(&folder, &game_data);
+
+    for language in ["en", "fr"] {
+        let conn = open_text_database(&output, language);
+
+        assert_eq!(text(&conn, "SELECT name FROM character_names WHERE id = ?1", 1001), Some(format!("Mamoru Endou ({language})")));
+        assert_eq!(text(&conn, "SELECT name FROM character_names_roma WHERE id = ?1", 1001), Some("Mamoru Endou".to_owned()));
+        assert_eq!(text(&conn, "SELECT description FROM character_descriptions WHERE id = ?1", 2001), Some(format!("The captain of Raimon ({language})")));
+        assert_eq!(text(&conn, "SELECT name FROM series_names WHERE id = ?1", common::SERIES_TEXT_ID), Some(format!("Raimon ({language})")));
+
+        // The variants are kept apart from the names
+        assert_eq!(text(&conn, "SELECT name FROM character_name_variants WHERE id = ?1 AND variant = 1", 1001), Some(format!("Endou ({language})")));
+
+        // Only the texts of the mined characters are written, not the ones of the filtered basara
+        assert_eq!(text(&conn, "SELECT name FROM character_names WHERE id = ?1", 1002), None);
+        assert_eq!(text(&conn, "SELECT description FROM character_descriptions WHERE id = ?1", 2002), None);
+    }
+
+    assert!(verify_databases(&output).unwrap().is_success());
+}
+
+#[test]
+fn a_missing_text_fails_the_verification() {
+    let folder = ExtractionFolder::with_game_files("verification", &["en", "fr"]);
+
+    let mut names = chara_names("fr");
+    names.remove(0);
+    folder.write_texts("fr", &names, &chara_descriptions("fr"));
+
+    let game_data = load(&folder, true).unwrap();
+    let output = write_sqlite(&folder, &game_data);
+
+    let report = verify_databases(&output).unwrap();
+
+    let failures: Vec<&String> = report.checks.iter().flat_map(|check| &check.failures).collect();
+    assert_eq!(failures, ["fr: name 1001 missing"]);
+}
+
+#[test]
+fn an_orphan_description_fails_the_verification() {
+    let folder = ExtractionFolder::with_game_files("orphan_description", &["en"]);
+
+    let game_data = load(&folder, true).unwrap();
+    let output = write_sqlite(&folder, &game_data);
+
+    open_text_database(&output, "en").execute("INSERT INTO character_descriptions (id, description) VALUES (9999, 'Nobody')", ()).unwrap();
+
+    let report = verify_databases(&output).unwrap();
+
+    let failures: Vec<&String> = report.checks.iter().flat_map(|check| &check.failures).collect();
+    assert_eq!(failures, ["en: description 9999 belongs to no character"]);
+}
+
+#[test]
+fn a_failed_database_write_is_returned() {
+    let folder = ExtractionFolder::with_game_files("failed_write", &["en"]);
+
+    let game_data = load(&folder, true).unwrap();
+    let output = folder.output_folder();
+    create_required_files(&output, &[OutputFormat::Sqlite], &["en".to_owned()]).unwrap();
+
+    // A table left by something else, which the names cannot be inserted into
+    open_text_database(&output, "en").execute("CREATE TABLE character_names (id INTEGER PRIMARY KEY)", ()).unwrap();
+
+    let result = create_sink(OutputFormat::Sqlite, &output, &Settings::default_values()).unwrap()
+        .write(&game_data);
+
+    assert!(result.is_err_and(|e| e.to_string().contains("character_names")));
+}
+
+#[test]
+fn an_interrupted_swap_is_recovered() {
+    let folder = ExtractionFolder::new("interrupted_swap");
+    let output = folder.root.join("output");
+
+    // The program stopped after moving the previous output away, before the staging folder replaced it
+    let backup = folder.root.join("output.tmp.old");
+    fs::create_dir_all(&backup).unwrap();
+    fs::write(backup.join(DATABASES[0]), "previous").unwrap();
+
+    let staging = prepare_staging_folder(&output, true).unwrap();
+
+    assert!(!backup.exists());
+    assert_eq!(fs::read_to_string(output.join(DATABASES[0])).unwrap(), "previous");
+    assert_eq!(fs::read_to_string(staging.join(DATABASES[0])).unwrap(), "previous");
+}
+
+#[test]
+fn legacy_heroes_are_matched_by_index() {
+    let folder = ExtractionFolder::with_game_files("legacy_diff", &["en"]);
+
+    let mut params = characters();
+    params[1].rarity = 6;
+    folder.write_characters(&chara_base(), &params);
+
+    let game_data = load(&folder, true).unwrap();
+    let output = write_sqlite(&folder, &game_data);
+
+    // The databases written before the rarity was stored had one table per kind of character
+    let legacy = folder.root.join("legacy.sqlite");
+    let conn = Connection::open(&legacy).unwrap();
+    conn.execute("ATTACH DATABASE ?1 AS current", [output.join(DATABASES[0]).to_string_lossy()]).unwrap();
+
+    for (table, view) in [("characters", "legendaries"), ("heroes", "heroes"), ("basaras", "basaras")] {
+        conn.execute(&format!(
+            "CREATE TABLE {table} AS SELECT index_id, name_id, description_id, element, main_position, alt_position, style, series_id,
+                lvl50_kick, lvl50_control, lvl50_technique, lvl50_pressure, lvl50_physical, lvl50_agility, lvl50_intelligence,
+                lvl99_kick, lvl99_control, lvl99_technique, lvl99_pressure, lvl99_physical, lvl99_agility, lvl99_intelligence
+            FROM current.{view}"
+        ), ()).unwrap();
+    }
+    drop(conn);
+
+    let game_diff = GameDiff::compute(&DiffSource::load(&legacy).unwrap(), &DiffSource::load(&folder.root).unwrap());
+
+    assert!(game_diff.is_empty(), "{}", game_diff.to_markdown());
+}
+
+#[test]
+fn a_missing_game_file_is_reported() {
+    let folder = ExtractionFolder::with_game_files("missing_file", &["en"]);
+    folder.remove(&format!("{}growth_table_config_{}.cfg.bin", ievr_dataminer::CHARA_ROOT_PATH, common::VERSION));
+
+    match load(&folder, false) {
+        Err(LoadError::MissingFiles(problems)) => {
+            assert!(matches!(problems.as_slice(), [FileProblem::Missing { identifier: "growth_table", .. }]), "{problems:?}");
+        },
+        other => panic!("expected a missing file, got {other:?}"),
+    }
+}
+
+#[test]
+fn an_unknown_language_is_rejected() {
+    let folder = ExtractionFolder::with_game_files("unknown_language", &["en", "fr"]);
+
+    // Only the requested language is parsed, and an unknown one stops the loading
+    let options = |languages: &[&str]| LoadOptions { strict: false, languages: languages.iter().map(|l| l.to_string()).collect() };
+    let game_data = GameData::load_with(&folder.root, &options(&["en"])).unwrap();
+    assert_eq!(game_data.text.keys().collect::<Vec<_>>(), ["en"]);
+
+    match GameData::load_with(&folder.root, &options(&["en", "english"])) {
+        Err(LoadError::Languages(LanguageError::Unknown { language, .. })) => assert_eq!(language, "english"),
+        other => panic!("expected an unknown language, got {other:?}"),
+    }
+}
+
+#[test]
+fn a_value_of_the_wrong_type_skips_its_row() {
+    let folder = ExtractionFolder::with_game_files("wrong_type", &["en"]);
+
+    // The rarity of the hero is stored as a float, as if the column had moved
+    let mut chara_params: Vec<_> = characters().iter().map(common::chara_param_row).collect();
+    chara_params[1].values[41] = vec![Value::Float(5.0)];
+    folder.write(&format!("{}chara_param_{}.cfg.bin", ievr_dataminer::CHARA_ROOT_PATH, common::VERSION), vec![
+        ("CHARA_PARAM_INFO", chara_params),
+    ]);
+
+    let game_data = load(&folder, false).unwrap();
+
+    let rarities: Vec<i32> = game_data.characters.iter().map(|c| c.rarity).collect();
+    assert_eq!(rarities, [0]);
+
+    let [anomaly] = game_data.report.anomalies.as_slice() else {
+        panic!("expected a single anomaly, got {:?}", game_data.report.anomalies);
+    };
+    assert_eq!((anomaly.table, anomaly.row, anomaly.column, anomaly.column_name), (Some("CHARA_PARAM_INFO"), Some(1), Some(41), Some("rarity")));
+    assert!(matches!(anomaly.kind, MiningErrorKind::UnexpectedType { .. }));
+
+    // The strict mode stops on the same anomaly
+    match load(&folder, true) {
+        Err(LoadError::Invalid(error)) => assert_eq!((error.table, error.row, error.column), (Some("CHARA_PARAM_INFO"), Some(1), Some(41))),
+        other => panic!("expected the strict mode to fail, got {other:?}"),
+    }
+}
+
+#[test]
+fn a_moved_column_fails_the_schema_check() {
+    let folder = ExtractionFolder::with_game_files("moved_column", &["en"]);
+
+    // The first row of a table is checked against its schema, here without the rarity column
+    let chara_params = characters().iter()
+        .map(|param| {
+            let mut row = common::chara_param_row(param);
+            row.values.truncate(41);
+            row
+        })
+        .collect();
+    folder.write(&format!("{}chara_param_{}.cfg.bin", ievr_dataminer::CHARA_ROOT_PATH, common::VERSION), vec![
+        ("CHARA_PARAM_INFO", chara_params),
+    ]);
+
+    // The whole table is skipped, with a single anomaly
+    let game_data = load(&folder, false).unwrap();
+    assert!(game_data.characters.is_empty());
+
+    let [anomaly] = game_data.report.anomalies.as_slice() else {
+        panic!("expected a single anomaly, got {:?}", game_data.report.anomalies);
+    };
+    assert_eq!((anomaly.table, anomaly.row, anomaly.column_name), (Some("CHARA_PARAM_INFO"), None, Some("rarity")));
+    assert!(matches!(anomaly.kind, MiningErrorKind::MissingColumn), "{anomaly}");
+
+    match load(&folder, true) {
+        Err(LoadError::Invalid(error)) => assert!(matches!(error.kind, MiningErrorKind::MissingColumn), "{error}"),
+        other => panic!("expected the strict mode to fail, got {other:?}"),
+    }
+}
+
+#[test]
+fn a_first_row_of_the_wrong_type_skips_the_table() {
+    let folder = ExtractionFolder::with_game_files("wrong_first_row", &["en"]);
+
+    // Unlike on the later rows, a value of the wrong type on the first row fails the schema check
+    let mut chara_params: Vec<_> = characters().iter().map(common::chara_param_row).collect();
+    chara_params[0].values[41] = vec![Value::Float(0.0)];
+    folder.write(&format!("{}chara_param_{}.cfg.bin", ievr_dataminer::CHARA_ROOT_PATH, common::VERSION), vec![
+        ("CHARA_PARAM_INFO", chara_params),
+    ]);
+
+    let game_data = load(&folder, false).unwrap();
+    assert!(game_data.characters.is_empty());
+
+    let [anomaly] = game_data.report.anomalies.as_slice() else {
+        panic!("expected a single anomaly, got {:?}", game_data.report.anomalies);
+    };
+    assert_eq!((anomaly.table, anomaly.row, anomaly.column), (Some("CHARA_PARAM_INFO"), None, Some(41)));
+    assert!(matches!(anomaly.kind, MiningErrorKind::SchemaMismatch { .. }), "{anomaly}");
+
+    // The rest of the data is still mined
+    assert_eq!(game_data.skills.len(), 2);
+    assert!(game_data.text["en"].chara_names.contains_key(&1001));
+
+    assert!(matches!(load(&folder, true), Err(LoadError::Invalid(_))));
+}
+
+#[test]
+fn duplicate_texts_keep_the_last_one() {
+    let folder = ExtractionFolder::with_game_files("duplicate_text", &["en"]);
+
+    let mut names = chara_names("en");
+    names.push(noun_row(1002, 0, "Gouenji"));
+    folder.write_texts("en", &names, &chara_descriptions("en"));
+
+    // A duplicate is only a warning, so the strict mode does not fail on it
+    let game_data = load(&folder, true).unwrap();
+    assert_eq!(game_data.text["en"].chara_names[&1002], "Gouenji");
+    assert!(game_data.report.anomalies.is_empty());
+
+    let [warning] = game_data.report.warnings.as_slice() else {
+        panic!("expected a single warning, got {:?}", game_data.report.warnings);
+    };
+    assert!(matches!(warning.kind, MiningErrorKind::DuplicateText { id: 1002, variant: None }));
+}
+
+#[test]
+fn duplicate_characters_and_skills_keep_the_last_one() {
+    let folder = ExtractionFolder::with_game_files("duplicate_rows", &["en"]);
+
+    let mut params = characters();
+    params.push(CharaParam { id: 1099, skills: [11, 0, 0, 0, 0, 0], ..params[1].clone() });
+    folder.write_characters(&chara_base(), &params);
+
+    let mut skills = common::skills();
+    skills.push(common::skill_row(10, 5099, 6099));
+    folder.write(&format!("{}skill_config_{}.cfg.bin", ievr_dataminer::SKILL_ROOT_PATH, common::VERSION), vec![
+        ("SKILL_CONFIG_INFO", skills),
+    ]);
+
+    // Like the duplicate texts, they are only warnings
+    let game_data = load(&folder, true).unwrap();
+    assert!(game_data.report.anomalies.is_empty());
+
+    let warnings: Vec<&MiningErrorKind> = game_data.report.warnings.iter().map(|warning| &warning.kind).collect();
+    assert!(matches!(warnings.as_slice(), [
+        MiningErrorKind::DuplicateCharacter { index: 1, rarity: 5 },
+        MiningErrorKind::DuplicateSkill { id: 10 },
+    ]), "{warnings:?}");
+
+    let output = write_sqlite(&folder, &game_data);
+
+    let conn = Connection::open(output.join(DATABASES[0])).unwrap();
+    let hero: (i32, i32) = conn.query_row(
+        "SELECT chara_param_id, COUNT(*) FROM characters WHERE index_id = 1 AND rarity = 5", [], |row| Ok((row.get(0)?, row.get(1)?))
+    ).unwrap();
+    assert_eq!(hero, (1099, 1));
+
+    let hero_skills: Vec<i32> = conn.prepare("SELECT skill_id FROM character_skills WHERE index_id = 1 AND rarity = 5").unwrap()
+        .query_map([], |row| row.get(0)).unwrap()
+        .collect::<Result<_, _>>().unwrap();
+    assert_eq!(hero_skills, [11]);
+
+    let conn = Connection::open(output.join(DATABASES[1])).unwrap();
+    let skill: (i32, i32) = conn.query_row("SELECT name_id, COUNT(*) FROM skills WHERE id = 10", [], |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+    assert_eq!(skill, (5099, 1));
+}
+
+#[test]
+fn characters_without_a_series_are_not_mined() {
+    let folder = ExtractionFolder::with_game_files("unknown_series", &["en"]);
+
+    let mut chara_base = chara_base();
+    chara_base[1] = common::chara_base_row(200, 2, 1002, 99, 2002);
+    let chara_params = [
+        characters(),
+        vec![CharaParam { id: 2000, chara_base_id: 200, rarity: 0, skills: [10; 6], ..characters()[0].clone() }],
+    ].concat();
+    folder.write_characters(&chara_base, &chara_params);
+
+    let game_data = load(&folder, true).unwrap();
+
+    let indices: Vec<i32> = game_data.characters.iter().map(|c| c.index).collect();
+    assert_eq!(indices, [1, 1]);
+}
+
+#[test]
+fn unread_columns_can_hold_any_value() {
+    let folder = ExtractionFolder::with_game_files("unread_columns", &["en"]);
+
+    // The game files hold many more columns than the dataminer reads
+    let mut names = chara_names("en");
+    names[0] = row(6, &[
+        (0, Value::Int(1001)),
+        (1, Value::Int(0)),
+        (2, Value::String("unused".to_owned())),
+        (3, Value::Float(1.5)),
+        (5, Value::String("Mamoru Endou (en)".to_owned())),
+    ]);
+    folder.write_texts("en", &names, &chara_descriptions("en"));
+
+    let game_data = load(&folder, true).unwrap();
+    assert_eq!(game_data.text["en"].chara_names[&1001], "Mamoru Endou (en)");
+}